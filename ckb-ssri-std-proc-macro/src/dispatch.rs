@@ -0,0 +1,466 @@
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{meta::ParseNestedMeta, GenericArgument, LitInt, LitStr, PathArguments, ReturnType, Type};
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::method_path;
+
+/// Names of the methods every SSRI dispatcher serves
+#[cfg(not(feature = "method-info"))]
+pub(crate) const RESERVED_METHODS: [&str; 4] = [
+    "SSRI.version",
+    "SSRI.get_methods",
+    "SSRI.has_methods",
+    "SSRI.get_method_levels",
+];
+/// Names of the methods every SSRI dispatcher serves
+#[cfg(feature = "method-info")]
+pub(crate) const RESERVED_METHODS: [&str; 5] = [
+    "SSRI.version",
+    "SSRI.get_methods",
+    "SSRI.has_methods",
+    "SSRI.get_method_levels",
+    "SSRI.get_method_info",
+];
+
+/// Execution levels a method can require, as accepted by `level = "..."`,
+/// with their `ckb_ssri_std::utils::context::ExecutionLevel` variants
+const LEVELS: [(&str, &str); 4] = [
+    ("code", "Code"),
+    ("script", "Script"),
+    ("transaction", "Transaction"),
+    ("chain", "Chain"),
+];
+
+/// Arguments accepted by `#[ssri_method(...)]`
+#[derive(Default)]
+pub(crate) struct MethodArgs {
+    level: Option<LitStr>,
+    args: Option<LitInt>,
+}
+
+impl MethodArgs {
+    pub(crate) fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("level") {
+            let level: LitStr = meta.value()?.parse()?;
+            level_index(&level)?;
+            self.level = Some(level);
+            Ok(())
+        } else if meta.path.is_ident("args") {
+            let args: LitInt = meta.value()?.parse()?;
+            args.base10_parse::<usize>()?;
+            self.args = Some(args);
+            Ok(())
+        } else {
+            Err(meta.error("unsupported ssri_method argument"))
+        }
+    }
+
+    /// The required execution level, `code` if none is given
+    pub(crate) fn level(&self) -> u8 {
+        self.level
+            .as_ref()
+            .map(|level| level_index(level).unwrap())
+            .unwrap_or(0)
+    }
+
+    /// The argument count declared with `args = N`, only accepted on the
+    /// expression arms of `ssri_methods!`
+    pub(crate) fn args(&self) -> Option<usize> {
+        self.args.as_ref().map(|args| args.base10_parse().unwrap())
+    }
+
+    /// Reject `args = N` on methods whose arguments are decoded from their
+    /// typed parameters
+    pub(crate) fn check_typed(&self) -> syn::Result<()> {
+        match &self.args {
+            Some(args) => Err(syn::Error::new(
+                args.span(),
+                "`args` is only accepted on expression arms of ssri_methods!; the argument \
+                 count of typed methods is their parameter count",
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+fn level_index(level: &LitStr) -> syn::Result<u8> {
+    LEVELS
+        .iter()
+        .position(|(name, _)| *name == level.value())
+        .map(|index| index as u8)
+        .ok_or_else(|| {
+            syn::Error::new(
+                level.span(),
+                "unknown execution level, expected one of `code`, `script`, `transaction` or `chain`",
+            )
+        })
+}
+
+/// Generate the `ckb_ssri_std::utils::version::ContractVersion` declared by
+/// `version = "1.2.0"`, `0.0.0` if none is given
+///
+/// The version is parsed by `ContractVersion::parse` in a constant, so that a
+/// malformed version fails the build at the literal.
+pub(crate) fn contract_version(version: Option<&LitStr>) -> TokenStream2 {
+    match version {
+        Some(version) => quote_spanned! {version.span()=>
+            {
+                const VERSION: ckb_ssri_std::utils::version::ContractVersion =
+                    ckb_ssri_std::utils::version::ContractVersion::parse(#version);
+                VERSION
+            }
+        },
+        None => quote!(ckb_ssri_std::utils::version::ContractVersion::new(0, 0, 0)),
+    }
+}
+
+/// Generate `body` guarded by a check of the execution level
+///
+/// The guard evaluates to `Err(SSRIError::SSRIMethodRequireHigherLevel)`
+/// converted into `error_ty` if the running script has less context than
+/// `level`. Methods of the `code` level are not guarded.
+pub(crate) fn guard_level(level: u8, error_ty: &TokenStream2, body: &TokenStream2) -> TokenStream2 {
+    if level == 0 {
+        return body.clone();
+    }
+    let variant = format_ident!("{}", LEVELS[level as usize].1);
+    quote! {
+        match ckb_ssri_std::utils::context::require_level(
+            ckb_ssri_std::utils::context::ExecutionLevel::#variant,
+        ) {
+            Ok(()) => #body,
+            Err(err) => Err(<#error_ty as From<ckb_ssri_std::SSRIError>>::from(err)),
+        }
+    }
+}
+
+/// Reject methods sharing a name or a method path with each other or with a
+/// reserved method
+///
+/// Method paths are truncated hashes, so distinct names may still collide; the
+/// error points at both methods involved.
+pub(crate) fn check_methods(methods: &[(String, Span)]) -> syn::Result<()> {
+    let mut errors: Option<syn::Error> = None;
+    let mut report = |span: Span, message: String| {
+        let err = syn::Error::new(span, message);
+        match errors.as_mut() {
+            Some(errors) => errors.combine(err),
+            None => errors = Some(err),
+        }
+    };
+
+    for (index, (name, span)) in methods.iter().enumerate() {
+        let path = method_path(name);
+        if let Some(reserved) = RESERVED_METHODS
+            .iter()
+            .find(|reserved| method_path(reserved) == path)
+        {
+            if reserved == name {
+                report(*span, format!("`{}` is reserved by SSRI and cannot be redefined", name));
+            } else {
+                report(
+                    *span,
+                    format!("method path of `{}` collides with the reserved `{}`", name, reserved),
+                );
+            }
+            continue;
+        }
+        for (other, other_span) in methods[..index].iter() {
+            if other == name {
+                report(*span, format!("duplicate SSRI method `{}`", name));
+                report(*other_span, format!("`{}` is first defined here", other));
+            } else if method_path(other) == path {
+                report(
+                    *span,
+                    format!("method path of `{}` collides with `{}`", name, other),
+                );
+                report(*other_span, format!("`{}` is defined here", other));
+            }
+        }
+    }
+
+    match errors {
+        Some(errors) => Err(errors),
+        None => Ok(()),
+    }
+}
+
+/// A parameter of an SSRI method, decoded from one argv entry
+pub(crate) struct MethodParam {
+    ty: Type,
+    by_ref: bool,
+}
+
+impl MethodParam {
+    /// A parameter of type `&T` is decoded as `T` and passed by reference
+    pub(crate) fn new(ty: &Type) -> Self {
+        match ty {
+            Type::Reference(reference) if reference.mutability.is_none() => MethodParam {
+                ty: reference.elem.as_ref().clone(),
+                by_ref: true,
+            },
+            ty => MethodParam {
+                ty: ty.clone(),
+                by_ref: false,
+            },
+        }
+    }
+}
+
+/// Extract the `index`-th type argument of a `Result<T, E>` return type
+pub(crate) fn result_type(output: &ReturnType, index: usize) -> Option<Type> {
+    let ReturnType::Type(_, ty) = output else {
+        return None;
+    };
+    let Type::Path(path) = ty.as_ref() else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Result" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.iter().nth(index)? {
+        GenericArgument::Type(ty) => Some(ty.clone()),
+        _ => None,
+    }
+}
+
+/// Render a type the way it is written in source, e.g. `Vec<[u8; 32]>`
+///
+/// The token stream of a type separates every token with a space; only the
+/// spaces between two words and after `,` or `;` are kept.
+fn type_name(ty: &Type) -> String {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '\'';
+    let tokens = ty.to_token_stream().to_string();
+    let mut name = String::new();
+    let mut chars = tokens.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ' ' {
+            let prev = name.chars().last().unwrap_or(' ');
+            let next = chars.peek().copied().unwrap_or(' ');
+            if !(prev == ',' || prev == ';' || is_word(prev) && is_word(next)) {
+                continue;
+            }
+        }
+        name.push(c);
+    }
+    name
+}
+
+/// Name, signature and execution level of an SSRI method, as served by
+/// `SSRI.get_method_info` and written to the interface manifest
+pub(crate) struct MethodSignature {
+    pub(crate) name: String,
+    pub(crate) args: Vec<String>,
+    /// The `Ok` type of the method, empty when it is not declared
+    pub(crate) output: String,
+    #[cfg_attr(not(feature = "manifest"), allow(dead_code))]
+    pub(crate) level: u8,
+}
+
+impl MethodSignature {
+    pub(crate) fn new(name: &str, level: u8, params: &[MethodParam], output: Option<&Type>) -> Self {
+        MethodSignature {
+            name: name.to_string(),
+            args: params.iter().map(|param| type_name(&param.ty)).collect(),
+            output: output.map(type_name).unwrap_or_default(),
+            level,
+        }
+    }
+
+    /// The name of the execution level, as accepted by `level = "..."`
+    #[cfg_attr(not(feature = "manifest"), allow(dead_code))]
+    pub(crate) fn level_name(&self) -> &'static str {
+        LEVELS[self.level as usize].0
+    }
+
+    /// Generate the `ckb_ssri_std::utils::dispatch::MethodInfo` of the method
+    pub(crate) fn info(&self) -> TokenStream2 {
+        let MethodSignature {
+            name, args, output, ..
+        } = self;
+        quote! {
+            ckb_ssri_std::utils::dispatch::MethodInfo {
+                name: #name,
+                args: &[#(#args),*],
+                output: #output,
+            }
+        }
+    }
+}
+
+/// Generate the call of `callee`, decoding `argv[1..]` into its parameters
+///
+/// The call evaluates to `Err(#invalid_args)` if the argument count does not
+/// match or an argument cannot be decoded.
+pub(crate) fn typed_call(
+    callee: &TokenStream2,
+    params: &[MethodParam],
+    argv: &TokenStream2,
+    invalid_args: &TokenStream2,
+) -> TokenStream2 {
+    let arg_count = params.len() + 1;
+    if params.is_empty() {
+        return quote! {
+            if (#argv).len() != #arg_count {
+                Err(#invalid_args)
+            } else {
+                (#callee)()
+            }
+        };
+    }
+
+    let values = (0..params.len())
+        .map(|index| format_ident!("arg{}", index))
+        .collect::<Vec<_>>();
+    let decodes = params.iter().enumerate().map(|(index, param)| {
+        let ty = &param.ty;
+        let argv_index = index + 1;
+        quote!(ckb_ssri_std::codec::decode_arg::<#ty>(&(#argv)[#argv_index]))
+    });
+    let call_args = params.iter().zip(values.iter()).map(|(param, value)| {
+        if param.by_ref {
+            quote!(&#value)
+        } else {
+            quote!(#value)
+        }
+    });
+
+    quote! {
+        if (#argv).len() != #arg_count {
+            Err(#invalid_args)
+        } else {
+            match (#(#decodes,)*) {
+                (#(Ok(#values),)*) => (#callee)(#(#call_args),*),
+                _ => Err(#invalid_args),
+            }
+        }
+    }
+}
+
+/// Guard an expression arm of `ssri_methods!` against a wrong argument count
+///
+/// With `#[ssri_method(args = N)]`, the arm evaluates to `Err(#invalid_args)`
+/// unless `argv` holds exactly `N` arguments after the method path. Without a
+/// declared count the body is left unguarded and must check `argv` itself.
+pub(crate) fn expression_call(
+    body: &TokenStream2,
+    args: Option<usize>,
+    argv: &TokenStream2,
+    invalid_args: &TokenStream2,
+) -> TokenStream2 {
+    match args {
+        Some(args) => {
+            let arg_count = args + 1;
+            quote! {
+                if (#argv).len() != #arg_count {
+                    Err(#invalid_args)
+                } else {
+                    #body
+                }
+            }
+        }
+        None => body.clone(),
+    }
+}
+
+/// Generate the `match` arms calling the given methods
+///
+/// Each method body evaluates to `Result<T, E>` where `T: SSRIEncode`, and its
+/// `Ok` value is encoded automatically.
+pub(crate) fn method_arms(method_keys: &[u64], method_bodies: &[TokenStream2]) -> TokenStream2 {
+    quote! {
+        #(
+            #method_keys => (#method_bodies).map(ckb_ssri_std::codec::SSRIEncode::into_output),
+        )*
+    }
+}
+
+/// Generate the dispatch block shared by `ssri_methods!` and `#[ssri_module]`
+///
+/// The block evaluates to `Result<Cow<'static, [u8]>, #error_ty>`. The reserved
+/// `SSRI.*` methods are served by `ckb_ssri_std::utils::dispatch`, with
+/// `method_levels` listing the required execution level of each method and,
+/// with the `method-info` feature, `method_infos` their signatures; any other
+/// method path is bound to `__method_path` and handed to `dispatch_method`.
+/// `version` is the contract version reported by `SSRI.version`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn expand(
+    argv: &TokenStream2,
+    version: &TokenStream2,
+    invalid_method: &TokenStream2,
+    invalid_args: &TokenStream2,
+    error_ty: &TokenStream2,
+    method_keys: &[u64],
+    method_levels: &[u8],
+    method_infos: &[TokenStream2],
+    dispatch_method: &TokenStream2,
+) -> TokenStream2 {
+    let method_paths = RESERVED_METHODS
+        .iter()
+        .map(method_path)
+        .chain(method_keys.iter().copied());
+    let method_levels = RESERVED_METHODS
+        .iter()
+        .map(|_| 0u8)
+        .chain(method_levels.iter().copied());
+    let method_count = RESERVED_METHODS.len() + method_keys.len();
+    let (method_infos, dispatch_method) = if cfg!(feature = "method-info") {
+        let reserved_infos = 0..RESERVED_METHODS.len();
+        (
+            quote! {
+                const METHOD_INFOS: [ckb_ssri_std::utils::dispatch::MethodInfo; #method_count] = [
+                    #(ckb_ssri_std::utils::dispatch::RESERVED_METHOD_INFOS[#reserved_infos],)*
+                    #(#method_infos,)*
+                ];
+            },
+            quote! {
+                match ckb_ssri_std::utils::dispatch::dispatch_method_info(
+                    __method_path,
+                    argv,
+                    &METHOD_PATHS,
+                    &METHOD_INFOS,
+                ) {
+                    Some(res) => res.map_err(|_| #invalid_args),
+                    None => #dispatch_method,
+                }
+            },
+        )
+    } else {
+        (quote!(), dispatch_method.clone())
+    };
+
+    quote! {
+        {
+            use alloc::borrow::Cow;
+            const METHOD_PATHS: [u64; #method_count] = [#(#method_paths,)*];
+            const METHOD_LEVELS: [u8; #method_count] = [#(#method_levels,)*];
+            #method_infos
+            let argv: &[ckb_std::env::Arg] = &(#argv)[..];
+            let res: Result<Cow<'static, [u8]>, #error_ty> =
+                match ckb_ssri_std::utils::dispatch::decode_method_path(argv) {
+                    Err(_) => Err(#invalid_method),
+                    Ok(__method_path) => {
+                        match ckb_ssri_std::utils::dispatch::dispatch_reserved(
+                            __method_path,
+                            argv,
+                            &METHOD_PATHS,
+                            &METHOD_LEVELS,
+                            #version,
+                        ) {
+                            Some(res) => res.map_err(|_| #invalid_args),
+                            None => #dispatch_method,
+                        }
+                    }
+                };
+            res
+        }
+    }
+}
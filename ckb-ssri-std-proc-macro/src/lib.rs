@@ -3,65 +3,102 @@
 extern crate alloc;
 extern crate proc_macro;
 
-use core::panic;
+#[cfg(feature = "manifest")]
+extern crate std;
+
+mod dispatch;
+mod manifest;
+mod module;
 
 use ckb_hash::blake2b_256;
 use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
-use syn::{parse::Parse, parse_macro_input, Expr, ExprLit, Ident, Lit, Token};
+use syn::{
+    parse::Parse, parse_macro_input, Attribute, DeriveInput, Expr, ExprLit, Ident, ItemMod, Lit,
+    LitStr, Pat, Token, Type,
+};
 
+use alloc::string::ToString;
 use alloc::vec;
 use alloc::vec::Vec;
 
-fn encode_u64_vector(val: impl AsRef<[u64]>) -> Vec<u8> {
-    let val = val.as_ref();
-    u32::to_le_bytes(val.len() as u32)
-        .into_iter()
-        .chain(val.iter().flat_map(|v| u64::to_le_bytes(*v)))
-        .collect()
-}
-
 fn method_path(name: impl AsRef<[u8]>) -> u64 {
     u64::from_le_bytes(blake2b_256(name)[0..8].try_into().unwrap())
 }
 
 struct Methods {
     argv: Expr,
-    invalid_method: Expr,
-    invalid_args: Expr,
-    method_keys: Vec<u64>,
+    invalid_method: Option<Expr>,
+    invalid_args: Option<Expr>,
+    error: Option<Type>,
+    version: Option<LitStr>,
+    method_names: Vec<LitStr>,
+    method_levels: Vec<u8>,
+    method_arg_counts: Vec<Option<usize>>,
     method_bodies: Vec<Expr>,
 }
 
 impl Parse for Methods {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        input.parse::<Ident>()?;
-        input.parse::<Token![:]>()?;
-        let argv = input.parse::<Expr>()?;
-        input.parse::<Token![,]>()?;
-        input.parse::<Ident>()?;
-        input.parse::<Token![:]>()?;
-        let invalid_method = input.parse::<Expr>()?;
-        input.parse::<Token![,]>()?;
-        input.parse::<Ident>()?;
-        input.parse::<Token![:]>()?;
-        let invalid_args = input.parse::<Expr>()?;
-        input.parse::<Token![,]>()?;
-
-        let mut method_keys = vec![];
+        let mut argv = None;
+        let mut invalid_method = None;
+        let mut invalid_args = None;
+        let mut error = None;
+        let mut version = None;
+        while input.peek(Ident) && input.peek2(Token![:]) {
+            let key = input.parse::<Ident>()?;
+            input.parse::<Token![:]>()?;
+            match key.to_string().as_str() {
+                "argv" => argv = Some(input.parse::<Expr>()?),
+                "invalid_method" => invalid_method = Some(input.parse::<Expr>()?),
+                "invalid_args" => invalid_args = Some(input.parse::<Expr>()?),
+                "error" => error = Some(input.parse::<Type>()?),
+                "version" => version = Some(input.parse::<LitStr>()?),
+                _ => return Err(syn::Error::new(key.span(), "unsupported ssri_methods! parameter")),
+            }
+            input.parse::<Token![,]>()?;
+        }
+        let Some(argv) = argv else {
+            return Err(input.error("ssri_methods! requires an `argv` parameter"));
+        };
+
+        let mut method_names = vec![];
+        let mut method_levels = vec![];
+        let mut method_arg_counts = vec![];
         let mut method_bodies = vec![];
         while !input.is_empty() {
+            let mut args = dispatch::MethodArgs::default();
+            for attr in input.call(Attribute::parse_outer)? {
+                if !attr.path().is_ident("ssri_method") {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "only #[ssri_method(...)] is supported on ssri_methods! arms",
+                    ));
+                }
+                attr.parse_nested_meta(|meta| args.parse(meta))?;
+            }
             let name = match input.parse::<Expr>()? {
                 Expr::Lit(ExprLit {
                     lit: Lit::Str(v), ..
-                }) => v.value(),
-                _ => panic!("method name should be a string"),
+                }) => v,
+                expr => {
+                    return Err(syn::Error::new_spanned(
+                        expr,
+                        "method name should be a string",
+                    ))
+                }
             };
             input.parse::<Token![=>]>()?;
             let body = input.parse::<Expr>()?;
             input.parse::<Token![,]>()?;
+            if let Expr::Closure(_) = body {
+                args.check_typed()?;
+            }
 
-            method_keys.push(method_path(name));
+            method_names.push(name);
+            method_levels.push(args.level());
+            method_arg_counts.push(args.args());
             method_bodies.push(body);
         }
 
@@ -69,95 +106,287 @@ impl Parse for Methods {
             argv,
             invalid_method,
             invalid_args,
-            method_keys,
+            error,
+            version,
+            method_names,
+            method_levels,
+            method_arg_counts,
             method_bodies,
         })
     }
 }
 
+/// Dispatch an SSRI call to one of the listed `"<path>" => <body>` arms
+///
+/// ```ignore
+/// let res: Cow<'static, [u8]> = ssri_methods!(
+///     argv: argv,
+///     error: Error,
+///     invalid_method: Error::SSRIMethodsNotFound,
+///     invalid_args: Error::SSRIMethodsArgsInvalid,
+///     version: "1.2.0",
+///     "UDT.name" => Ok(PausableUDT::name()?),
+/// )?;
+/// ```
+///
+/// Only `argv` is required. `error` is the error type of the dispatch result
+/// and defaults to `ckb_ssri_std::SSRIError`. `version` is the
+/// `major.minor.patch` version of the interface reported by `SSRI.version`,
+/// `0.0.0` when omitted. `invalid_method` and
+/// `invalid_args` are the errors reported for unknown methods and malformed
+/// arguments; when omitted they are converted from the matching `SSRIError`
+/// variants, which requires `error` to implement `From<SSRIError>`.
+///
+/// Each body evaluates to `Result<T, error>` where `T` implements
+/// `ckb_ssri_std::codec::SSRIEncode`; the `Ok` value is encoded into the
+/// returned `Cow<'static, [u8]>`.
+///
+/// A body may also be a closure with typed arguments, e.g.
+/// `"UDT.balance" => |lock: Script| balance(lock)`. Its arguments are decoded
+/// from `argv[1..]` like the parameters of an `#[ssri_method]`, and a wrong
+/// argument count or a malformed argument yields `invalid_args` instead of a
+/// panic. Other bodies receive `argv` as is; they can declare their argument
+/// count with `#[ssri_method(args = 2)] "UDT.allowance" => ...`, and then yield
+/// `invalid_args` unless `argv` holds exactly that many arguments after the
+/// method path. The reserved `SSRI.*` methods validate their arguments the
+/// same way.
+///
+/// An arm can require an execution level with
+/// `#[ssri_method(level = "transaction")] "UDT.transfer" => ...`, see
+/// `#[ssri_method]`.
+///
+/// With the `method-info` feature of `ckb-ssri-std`, the reserved
+/// `SSRI.get_method_info` reports the argument types of closure arms, and
+/// their return type if the closure declares `-> Result<T, E>`; other arms are
+/// reported with their name only. The same signatures are written to the
+/// interface manifest with the `manifest` feature of `ckb-ssri-std`.
 #[proc_macro]
 pub fn ssri_methods(input: TokenStream) -> TokenStream {
     let Methods {
         argv,
         invalid_method,
         invalid_args,
-        method_keys,
+        error,
+        version,
+        method_names,
+        method_levels,
+        method_arg_counts,
         method_bodies,
     } = parse_macro_input!(input as Methods);
 
-    let version_path = method_path("SSRI.version");
-    let get_methods_path = method_path("SSRI.get_methods");
-    let has_methods_path = method_path("SSRI.has_methods");
+    let error_ty = match error {
+        Some(error) => quote!(#error),
+        None => quote!(ckb_ssri_std::SSRIError),
+    };
+    let invalid_method = match invalid_method {
+        Some(invalid_method) => quote!(#invalid_method),
+        None => quote!(<#error_ty as From<ckb_ssri_std::SSRIError>>::from(
+            ckb_ssri_std::SSRIError::SSRIMethodsNotFound
+        )),
+    };
+    let invalid_args = match invalid_args {
+        Some(invalid_args) => quote!(#invalid_args),
+        None => quote!(<#error_ty as From<ckb_ssri_std::SSRIError>>::from(
+            ckb_ssri_std::SSRIError::SSRIMethodsArgsInvalid
+        )),
+    };
 
-    let raw_methods = encode_u64_vector(
-        [version_path, get_methods_path, has_methods_path]
+    if let Err(err) = dispatch::check_methods(
+        &method_names
             .iter()
-            .chain(method_keys.iter())
-            .copied()
+            .map(|name| (name.value(), name.span()))
             .collect::<Vec<_>>(),
-    );
-    let raw_methods_len = raw_methods.len();
+    ) {
+        let errors = err.to_compile_error();
+        return TokenStream::from(quote!({ #errors }));
+    }
+    let method_keys = method_names
+        .iter()
+        .map(|name| method_path(name.value()))
+        .collect::<Vec<_>>();
 
-    TokenStream::from(quote! {
-        {
-            use alloc::{borrow::Cow, vec::Vec};
-            use ckb_std::high_level::decode_hex;
-            const RAW_METHODS: [u8; #raw_methods_len] = [#(#raw_methods,)*];
-            let res: Result<Cow<'static, [u8]>, Error> = match u64::from_le_bytes(
-                decode_hex(&(#argv)[0])?.try_into().map_err(|_| #invalid_method)?,
-            ) {
-                #version_path => Ok(Cow::from(&[0][..])),
-                #get_methods_path => {
-                    let offset = usize::min((u64::from_le_bytes(
-                        decode_hex(&(#argv)[1])?
-                            .try_into()
-                            .map_err(|_| #invalid_args)?
-                    ) as usize * 8), #raw_methods_len - 4);
-
-                    // If second argument is 0, take all remaining methods
-                    let second_arg = u64::from_le_bytes(
-                        decode_hex(&(#argv)[2])?
-                            .try_into()
-                            .map_err(|_| #invalid_args)?
-                    );
-
-                    let mut raw_result: Vec<u8>;
-                    if second_arg == 0 {
-                        // Take all remaining methods from offset
-                        raw_result = RAW_METHODS[(offset + 4)..].to_vec();
-                        let method_count = (RAW_METHODS.len() - (offset + 4)) / 8;
-                        let mut result = (method_count as u32).to_le_bytes().to_vec();
-                        result.extend_from_slice(&raw_result);
-                        Ok(Cow::from(result))
-                    } else {
-                        let limit = usize::min((offset + (second_arg as usize * 8)), #raw_methods_len - 4);
-                        raw_result = RAW_METHODS[(offset + 4)..(limit + 4)].to_vec();
-                        let method_count = (limit - offset) / 8;
-                        let mut result = (method_count as u32).to_le_bytes().to_vec();
-                        result.extend_from_slice(&raw_result);
-                        Ok(Cow::from(result))
-                    }
-                },
-                #has_methods_path => {
-                    let mut result = Vec::new();
-                    let matches = decode_hex(&(#argv)[1])?[4..].chunks(8).map(|path| {
-                        match RAW_METHODS[4..]
-                            .chunks(8)
-                            .find(|v| v == &path) {
-                                Some(_) => 1,
-                                None => 0,
-                            }
-                    }).collect::<Vec<_>>();
-                    result.extend_from_slice(&(matches.len() as u32).to_le_bytes());
-                    result.extend_from_slice(&matches);
-                    Ok(Cow::from(result))
-                },
-                #(
-                    #method_keys => #method_bodies,
-                )*
+    let mut typed_bodies = Vec::<TokenStream2>::new();
+    let mut signatures = Vec::<dispatch::MethodSignature>::new();
+    for (((body, level), arg_count), name) in method_bodies
+        .iter()
+        .zip(method_levels.iter())
+        .zip(method_arg_counts.iter())
+        .zip(method_names.iter())
+    {
+        let body = match body {
+            Expr::Closure(closure) => {
+                let mut params = vec![];
+                for input in closure.inputs.iter() {
+                    let Pat::Type(pat_type) = input else {
+                        return TokenStream::from(
+                            syn::Error::new_spanned(
+                                input,
+                                "arguments of ssri_methods! closures need a type annotation",
+                            )
+                            .to_compile_error(),
+                        );
+                    };
+                    params.push(dispatch::MethodParam::new(&pat_type.ty));
+                }
+                let output = dispatch::result_type(&closure.output, 0);
+                signatures.push(dispatch::MethodSignature::new(
+                    &name.value(),
+                    *level,
+                    &params,
+                    output.as_ref(),
+                ));
+                dispatch::typed_call(&quote!(#closure), &params, &quote!(argv), &invalid_args)
+            }
+            body => {
+                signatures.push(dispatch::MethodSignature::new(&name.value(), *level, &[], None));
+                dispatch::expression_call(&quote!(#body), *arg_count, &quote!(argv), &invalid_args)
+            }
+        };
+        typed_bodies.push(dispatch::guard_level(*level, &error_ty, &body));
+    }
+    let method_bodies = typed_bodies;
+    let method_arms = dispatch::method_arms(&method_keys, &method_bodies);
+    let method_infos = signatures.iter().map(|signature| signature.info()).collect::<Vec<_>>();
+    if let Err(err) = manifest::write(&manifest::methods_unit(&signatures), &signatures) {
+        let errors = err.to_compile_error();
+        return TokenStream::from(quote!({ #errors }));
+    }
+
+    let version = dispatch::contract_version(version.as_ref());
+    TokenStream::from(dispatch::expand(
+        &quote!(#argv),
+        &version,
+        &invalid_method,
+        &invalid_args,
+        &error_ty,
+        &method_keys,
+        &method_levels,
+        &method_infos,
+        &quote! {
+            match __method_path {
+                #method_arms
                 _ => Err(#invalid_method),
-            };
-            res
+            }
+        },
+    ))
+}
+
+/// Compute the method path of an SSRI method name at compile time
+///
+/// ```ignore
+/// const UDT_NAME: u64 = ssri_method_path!("UDT.name");
+/// ```
+#[proc_macro]
+pub fn ssri_method_path(input: TokenStream) -> TokenStream {
+    let name = parse_macro_input!(input as LitStr);
+    let path = method_path(name.value());
+    TokenStream::from(quote!(#path))
+}
+
+/// Expose the `#[ssri_method]` functions of an inline module as SSRI methods
+///
+/// Every `pub` function marked with `#[ssri_method]` is registered under the
+/// path `"<Module>.<fn>"`, where `<Module>` defaults to the module name and can
+/// be overridden with `#[ssri_module(name = "UDT")]`. The version of the
+/// interface reported by `SSRI.version` is declared with
+/// `#[ssri_module(version = "1.2.0")]` and defaults to `0.0.0`. The macro
+/// appends the following items to the module:
+///
+/// - `SSRI_METHOD_NAMES`: the full names of the exposed methods
+/// - `SSRI_METHOD_PATHS`: the method paths derived from these names
+/// - `SSRI_METHOD_LEVELS`: the execution levels required by the methods
+/// - `SSRI_CONTRACT_VERSION`: the declared version of the interface
+/// - `SSRI_METHOD_INFOS`: the names and signatures of the methods, only with
+///   the `method-info` feature of `ckb-ssri-std`
+/// - `ssri_dispatch(argv)`: the dispatcher serving the exposed methods together
+///   with the reserved `SSRI.*` methods
+///
+/// Each parameter of a method is decoded from the matching hex argument in
+/// `argv[1..]` through `ckb_ssri_std::codec::SSRIDecode`; a parameter of type
+/// `&T` is decoded as `T` and passed by reference. A wrong argument count or a
+/// malformed argument yields `SSRIError::SSRIMethodsArgsInvalid` instead of a
+/// panic. Methods return `Result<T, E>` where `T` implements
+/// `ckb_ssri_std::codec::SSRIEncode`, which encodes the result. With the
+/// `manifest` feature of `ckb-ssri-std`, the methods are written to the
+/// interface manifest in `OUT_DIR` under the unit `<Module>`. All methods of
+/// a module share the error type `E`, which must implement `From<SysError>` and
+/// `From<ckb_ssri_std::SSRIError>`.
+#[proc_macro_attribute]
+pub fn ssri_module(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut args = module::ModuleArgs::default();
+    let args_parser = syn::meta::parser(|meta| args.parse(meta));
+    parse_macro_input!(attr with args_parser);
+    let item_mod = parse_macro_input!(item as ItemMod);
+
+    match module::expand(args, item_mod) {
+        Ok(tokens) => TokenStream::from(tokens),
+        Err(err) => TokenStream::from(err.to_compile_error()),
+    }
+}
+
+/// Mark a function inside an `#[ssri_module]` as an exposed SSRI method
+///
+/// The attribute is read by the enclosing `#[ssri_module]` and leaves the
+/// function untouched; it has no effect outside of an `#[ssri_module]`.
+///
+/// `#[ssri_method(level = "transaction")]` declares the execution level the
+/// method needs, one of `code` (the default), `script`, `transaction` and
+/// `chain`. The dispatcher checks it with
+/// `ckb_ssri_std::utils::context::require_level` before decoding the arguments,
+/// failing with `SSRIError::SSRIMethodRequireHigherLevel` converted into the
+/// error type of the method, and reports it through the reserved
+/// `SSRI.get_method_levels`.
+#[proc_macro_attribute]
+pub fn ssri_method(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut args = dispatch::MethodArgs::default();
+    let args_parser = syn::meta::parser(|meta| args.parse(meta));
+    parse_macro_input!(attr with args_parser);
+    item
+}
+
+/// Derive `ckb_ssri_std::codec::SSRIEncode` for a `serde_molecule` type
+///
+/// The type must implement `serde::Serialize`; it is encoded as a molecule
+/// table with `serde_molecule::to_vec`.
+///
+/// # Panics
+///
+/// `SSRIEncode::encode` returns the bytes without an error, so the generated
+/// `encode` panics if `serde_molecule` rejects the type. This happens for
+/// shapes molecule has no encoding for, such as unit struct fields, whatever
+/// the value; a single encoding in a test reveals it.
+#[proc_macro_derive(SSRIEncode)]
+pub fn derive_ssri_encode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    TokenStream::from(quote! {
+        impl #impl_generics ckb_ssri_std::codec::SSRIEncode for #ident #ty_generics #where_clause {
+            fn encode(&self) -> alloc::vec::Vec<u8> {
+                ckb_ssri_std::__private::serde_molecule::to_vec(self, false)
+                    .expect("the type should have a molecule encoding")
+            }
+        }
+    })
+}
+
+/// Derive `ckb_ssri_std::codec::SSRIDecode` for a `serde_molecule` type
+///
+/// The type must implement `serde::Deserialize`; it is decoded as a molecule
+/// table with `serde_molecule::from_slice`.
+#[proc_macro_derive(SSRIDecode)]
+pub fn derive_ssri_decode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    TokenStream::from(quote! {
+        impl #impl_generics ckb_ssri_std::codec::SSRIDecode for #ident #ty_generics #where_clause {
+            fn decode(raw: &[u8]) -> Result<Self, ckb_ssri_std::SSRIError> {
+                ckb_ssri_std::__private::serde_molecule::from_slice(raw, false)
+                    .map_err(|_| ckb_ssri_std::SSRIError::SSRIMethodsArgsInvalid)
+            }
         }
     })
 }
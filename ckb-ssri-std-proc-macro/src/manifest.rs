@@ -0,0 +1,222 @@
+//! Interface manifest of the SSRI methods, written at build time
+//!
+//! With the `manifest` feature, every `#[ssri_module]` and `ssri_methods!`
+//! registers its methods under a unit, and the methods of all the units of the
+//! crate being built are written to `$OUT_DIR/ssri-manifest.json`, one JSON
+//! object per line:
+//!
+//! ```json
+//! {"methods":[
+//! {"name":"UDT.balance","path":"0x…","args":["Script"],"output":"u128","level":"script"}
+//! ]}
+//! ```
+//!
+//! `path` is the method path as passed in `argv[0]`, and `output` is empty when
+//! the return type is not declared. Cargo only sets `OUT_DIR` for packages with
+//! a build script, so nothing is written for packages without one.
+//!
+//! Only the manifest written by `cargo build` is reliable. The units are kept
+//! in the process expanding the macros, so a long-lived one, such as the
+//! proc-macro server of rust-analyzer, keeps the units of code edited or
+//! removed since it started and may overwrite the manifest with them.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::dispatch::MethodSignature;
+use crate::method_path;
+
+/// Name of the manifest unit of an `ssri_methods!` invocation, derived from
+/// the names of its methods
+pub(crate) fn methods_unit(signatures: &[MethodSignature]) -> String {
+    let names = signatures
+        .iter()
+        .map(|signature| signature.name.as_str())
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("ssri_methods-{:016x}", method_path(names))
+}
+
+#[cfg(not(feature = "manifest"))]
+pub(crate) fn write(_unit: &str, _signatures: &[MethodSignature]) -> syn::Result<()> {
+    Ok(())
+}
+
+/// Methods registered so far in this compiler process, by `OUT_DIR` and unit
+///
+/// All the macros of a crate expand in the compiler process building it, which
+/// loads this crate once, so the manifest is regenerated from the methods of
+/// the current build only: the methods of a renamed or removed unit don't
+/// survive it. This only holds for processes expanding a single build, see the
+/// module documentation.
+#[cfg(feature = "manifest")]
+static UNITS: std::sync::Mutex<
+    alloc::collections::BTreeMap<std::path::PathBuf, alloc::collections::BTreeMap<String, String>>,
+> = std::sync::Mutex::new(alloc::collections::BTreeMap::new());
+
+/// Register the methods of `unit` and regenerate the manifest
+///
+/// # Errors
+///
+/// * Returns an error spanning the macro invocation if the manifest cannot be
+///   written
+#[cfg(feature = "manifest")]
+pub(crate) fn write(unit: &str, signatures: &[MethodSignature]) -> syn::Result<()> {
+    use std::path::PathBuf;
+
+    let Some(out_dir) = std::env::var_os("OUT_DIR") else {
+        return Ok(());
+    };
+    let out_dir = PathBuf::from(out_dir);
+    let lines = signatures
+        .iter()
+        .map(|signature| method_line(signature) + "\n")
+        .collect::<String>();
+
+    let mut units = UNITS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let crate_units = units.entry(out_dir.clone()).or_default();
+    crate_units.insert(unit.into(), lines);
+    write_manifest(&out_dir.join("ssri-manifest.json"), crate_units.values()).map_err(|err| {
+        syn::Error::new(
+            proc_macro2::Span::call_site(),
+            format!(
+                "failed to write the SSRI manifest to `{}`: {}",
+                out_dir.display(),
+                err
+            ),
+        )
+    })
+}
+
+/// Write the manifest of the `lines` of all units to `manifest`
+///
+/// The manifest is written to a temporary file first and then renamed, so that
+/// concurrent builds sharing the `OUT_DIR`, such as the library and its tests,
+/// never leave a partial manifest.
+#[cfg(feature = "manifest")]
+fn write_manifest<'a>(
+    manifest: &std::path::Path,
+    units: impl Iterator<Item = &'a String>,
+) -> std::io::Result<()> {
+    use std::fs;
+
+    let temporary = manifest.with_extension(format!("json.{}.tmp", std::process::id()));
+    fs::write(&temporary, merge_units(units))?;
+    fs::rename(&temporary, manifest)
+}
+
+/// Merge the `lines` of all units into the content of the manifest
+///
+/// Methods registered by several units are listed once, in the order they are
+/// first registered.
+#[cfg(feature = "manifest")]
+fn merge_units<'a>(units: impl Iterator<Item = &'a String>) -> String {
+    let mut methods = Vec::<&str>::new();
+    for lines in units {
+        for line in lines.lines() {
+            if !line.is_empty() && !methods.contains(&line) {
+                methods.push(line);
+            }
+        }
+    }
+    format!("{{\"methods\":[\n{}\n]}}\n", methods.join(",\n"))
+}
+
+/// Encode a method as a single-line JSON object
+#[cfg(feature = "manifest")]
+fn method_line(signature: &MethodSignature) -> String {
+    let args = signature
+        .args
+        .iter()
+        .map(|arg| json_string(arg))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "{{\"name\":{},\"path\":\"0x{}\",\"args\":[{}],\"output\":{},\"level\":\"{}\"}}",
+        json_string(&signature.name),
+        method_path(&signature.name)
+            .to_le_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>(),
+        args,
+        json_string(&signature.output),
+        signature.level_name(),
+    )
+}
+
+#[cfg(feature = "manifest")]
+fn json_string(value: &str) -> String {
+    let mut json = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(all(test, feature = "manifest"))]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn encodes_methods_as_json_lines() {
+        let signature = MethodSignature {
+            name: "UDT.transfer".to_string(),
+            args: vec!["Vec<Script>".to_string(), "Vec<u128>".to_string()],
+            output: "Transaction".to_string(),
+            level: 2,
+        };
+        assert_eq!(
+            method_line(&signature),
+            format!(
+                "{{\"name\":\"UDT.transfer\",\"path\":\"0x{:016x}\",\"args\":[\"Vec<Script>\",\"Vec<u128>\"],\"output\":\"Transaction\",\"level\":\"transaction\"}}",
+                method_path("UDT.transfer").swap_bytes()
+            )
+        );
+
+        let signature = MethodSignature {
+            name: "UDT.pause".to_string(),
+            args: Vec::new(),
+            output: String::new(),
+            level: 0,
+        };
+        assert_eq!(
+            method_line(&signature),
+            format!(
+                "{{\"name\":\"UDT.pause\",\"path\":\"0x{:016x}\",\"args\":[],\"output\":\"\",\"level\":\"code\"}}",
+                method_path("UDT.pause").swap_bytes()
+            )
+        );
+    }
+
+    #[test]
+    fn escapes_json_strings() {
+        assert_eq!(json_string("UDT.name"), "\"UDT.name\"");
+        assert_eq!(json_string("\"\\"), "\"\\\"\\\\\"");
+        assert_eq!(json_string("\n\u{1f}"), "\"\\u000a\\u001f\"");
+        assert_eq!(json_string("Σ"), "\"Σ\"");
+    }
+
+    #[test]
+    fn merges_the_methods_of_all_units() {
+        let units = [
+            "{\"name\":\"A.a\"}\n{\"name\":\"A.b\"}\n".to_string(),
+            String::new(),
+            "{\"name\":\"A.b\"}\n{\"name\":\"B.a\"}\n".to_string(),
+        ];
+        assert_eq!(
+            merge_units(units.iter()),
+            "{\"methods\":[\n{\"name\":\"A.a\"},\n{\"name\":\"A.b\"},\n{\"name\":\"B.a\"}\n]}\n"
+        );
+        assert_eq!(merge_units(core::iter::empty()), "{\"methods\":[\n\n]}\n");
+    }
+}
@@ -0,0 +1,249 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, ToTokens};
+use syn::{
+    meta::ParseNestedMeta, spanned::Spanned, Attribute, FnArg, Item, ItemFn, ItemMod, LitStr,
+    Type, Visibility,
+};
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::dispatch::{self, MethodArgs, MethodParam, MethodSignature};
+use crate::{manifest, method_path};
+
+/// Arguments accepted by `#[ssri_module(...)]`
+#[derive(Default)]
+pub(crate) struct ModuleArgs {
+    name: Option<LitStr>,
+    version: Option<LitStr>,
+}
+
+impl ModuleArgs {
+    pub(crate) fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("name") {
+            self.name = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("version") {
+            self.version = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("unsupported ssri_module argument"))
+        }
+    }
+}
+
+struct ModuleMethod {
+    ident: syn::Ident,
+    level: u8,
+    params: Vec<MethodParam>,
+    output_ty: Type,
+    error_ty: Type,
+}
+
+fn is_ssri_method(attr: &Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .map(|segment| segment.ident == "ssri_method")
+        .unwrap_or(false)
+}
+
+fn parse_method(item_fn: &ItemFn) -> syn::Result<ModuleMethod> {
+    let mut args = MethodArgs::default();
+    for attr in item_fn.attrs.iter().filter(|attr| is_ssri_method(attr)) {
+        if !matches!(attr.meta, syn::Meta::Path(_)) {
+            attr.parse_nested_meta(|meta| args.parse(meta))?;
+        }
+    }
+    args.check_typed()?;
+
+    if !matches!(item_fn.vis, Visibility::Public(_)) {
+        return Err(syn::Error::new(
+            item_fn.sig.ident.span(),
+            "#[ssri_method] functions must be declared `pub`",
+        ));
+    }
+    let mut params = vec![];
+    for input in item_fn.sig.inputs.iter() {
+        let FnArg::Typed(pat_type) = input else {
+            return Err(syn::Error::new(
+                input.span(),
+                "#[ssri_method] functions cannot take `self`",
+            ));
+        };
+        params.push(MethodParam::new(&pat_type.ty));
+    }
+    let (Some(output_ty), Some(error_ty)) = (
+        dispatch::result_type(&item_fn.sig.output, 0),
+        dispatch::result_type(&item_fn.sig.output, 1),
+    ) else {
+        return Err(syn::Error::new(
+            item_fn.sig.output.span(),
+            "#[ssri_method] functions must return `Result<T, E>`",
+        ));
+    };
+
+    Ok(ModuleMethod {
+        ident: item_fn.sig.ident.clone(),
+        level: args.level(),
+        params,
+        output_ty,
+        error_ty,
+    })
+}
+
+pub(crate) fn expand(args: ModuleArgs, mut item_mod: ItemMod) -> syn::Result<TokenStream2> {
+    let module_name = match &args.name {
+        Some(name) => name.value(),
+        None => item_mod.ident.to_string(),
+    };
+    let Some((_, items)) = item_mod.content.as_mut() else {
+        return Err(syn::Error::new(
+            item_mod.span(),
+            "#[ssri_module] requires an inline module body",
+        ));
+    };
+
+    let mut methods: Vec<ModuleMethod> = vec![];
+    for item in items.iter_mut() {
+        let Item::Fn(item_fn) = item else {
+            continue;
+        };
+        if !item_fn.attrs.iter().any(is_ssri_method) {
+            continue;
+        }
+        methods.push(parse_method(item_fn)?);
+        // `#[ssri_method]` is consumed here, so the module body compiles
+        // without importing it
+        item_fn.attrs.retain(|attr| !is_ssri_method(attr));
+    }
+
+    let Some(first) = methods.first() else {
+        return Err(syn::Error::new(
+            item_mod.ident.span(),
+            "#[ssri_module] requires at least one #[ssri_method] function",
+        ));
+    };
+    let error_ty = first.error_ty.to_token_stream();
+    let error_ty_str = error_ty.to_string();
+    for method in methods.iter().skip(1) {
+        if method.error_ty.to_token_stream().to_string() != error_ty_str {
+            return Err(syn::Error::new(
+                method.error_ty.span(),
+                format!(
+                    "all #[ssri_method] functions in a module must share the error type `{}`",
+                    error_ty_str
+                ),
+            ));
+        }
+    }
+
+    let argv = quote!(argv);
+    let invalid_method =
+        quote!(<#error_ty as From<ckb_ssri_std::SSRIError>>::from(ckb_ssri_std::SSRIError::SSRIMethodsNotFound));
+    let invalid_args =
+        quote!(<#error_ty as From<ckb_ssri_std::SSRIError>>::from(ckb_ssri_std::SSRIError::SSRIMethodsArgsInvalid));
+    let method_names = methods
+        .iter()
+        .map(|method| format!("{}.{}", module_name, method.ident))
+        .collect::<Vec<String>>();
+    dispatch::check_methods(
+        &method_names
+            .iter()
+            .zip(methods.iter())
+            .map(|(name, method)| (name.clone(), method.ident.span()))
+            .collect::<Vec<_>>(),
+    )?;
+    let method_keys = method_names.iter().map(method_path).collect::<Vec<_>>();
+    let method_levels = methods.iter().map(|method| method.level).collect::<Vec<_>>();
+    let signatures = methods
+        .iter()
+        .zip(method_names.iter())
+        .map(|(method, name)| {
+            MethodSignature::new(name, method.level, &method.params, Some(&method.output_ty))
+        })
+        .collect::<Vec<_>>();
+    manifest::write(&module_name, &signatures)?;
+    let method_infos = signatures.iter().map(|signature| signature.info()).collect::<Vec<_>>();
+    let method_bodies = methods
+        .iter()
+        .map(|method| {
+            let ident = &method.ident;
+            let call = dispatch::typed_call(&quote!(#ident), &method.params, &argv, &invalid_args);
+            dispatch::guard_level(method.level, &error_ty, &call)
+        })
+        .collect::<Vec<_>>();
+    let version = dispatch::contract_version(args.version.as_ref());
+    let dispatch = dispatch::expand(
+        &argv,
+        &quote!(SSRI_CONTRACT_VERSION),
+        &invalid_method,
+        &invalid_args,
+        &error_ty,
+        &method_keys,
+        &method_levels,
+        &method_infos,
+        &quote!(ssri_call(__method_path, argv).unwrap_or_else(|| Err(#invalid_method))),
+    );
+    let method_count = method_keys.len();
+
+    items.push(syn::parse_quote! {
+        /// Names of the SSRI methods exposed by this module
+        pub const SSRI_METHOD_NAMES: [&str; #method_count] = [#(#method_names,)*];
+    });
+    items.push(syn::parse_quote! {
+        /// Method paths of the SSRI methods exposed by this module
+        pub const SSRI_METHOD_PATHS: [u64; #method_count] = [#(#method_keys,)*];
+    });
+    items.push(syn::parse_quote! {
+        /// Execution levels required by the SSRI methods exposed by this module,
+        /// as `ckb_ssri_std::utils::context::ExecutionLevel` values
+        pub const SSRI_METHOD_LEVELS: [u8; #method_count] = [#(#method_levels,)*];
+    });
+    items.push(syn::parse_quote! {
+        /// Version of the interface of this module reported by `SSRI.version`
+        pub const SSRI_CONTRACT_VERSION: ckb_ssri_std::utils::version::ContractVersion = #version;
+    });
+    if cfg!(feature = "method-info") {
+        items.push(syn::parse_quote! {
+            /// Names and signatures of the SSRI methods exposed by this module
+            pub const SSRI_METHOD_INFOS: [ckb_ssri_std::utils::dispatch::MethodInfo; #method_count] =
+                [#(#method_infos,)*];
+        });
+    }
+    items.push(syn::parse_quote! {
+        /// Call the SSRI method of this module identified by `path`
+        ///
+        /// `argv[1..]` holds the arguments of the method. Returns `None` if the
+        /// module has no method with this path; the reserved `SSRI.*` methods
+        /// are not handled here.
+        pub fn ssri_call(
+            path: u64,
+            argv: &[ckb_std::env::Arg],
+        ) -> Option<Result<alloc::borrow::Cow<'static, [u8]>, #error_ty>> {
+            match path {
+                #(
+                    #method_keys => Some(
+                        (#method_bodies).map(ckb_ssri_std::codec::SSRIEncode::into_output),
+                    ),
+                )*
+                _ => None,
+            }
+        }
+    });
+    items.push(syn::parse_quote! {
+        /// Dispatch an SSRI call to the methods of this module
+        ///
+        /// `argv[0]` is the hex encoded method path and `argv[1..]` the arguments
+        /// of the method.
+        pub fn ssri_dispatch(
+            argv: &[ckb_std::env::Arg],
+        ) -> Result<alloc::borrow::Cow<'static, [u8]>, #error_ty> {
+            #dispatch
+        }
+    });
+
+    Ok(item_mod.into_token_stream())
+}
@@ -0,0 +1,496 @@
+//! Encoding and decoding of SSRI method arguments and results
+//!
+//! SSRI methods receive their arguments as hex strings in `argv[1..]` and
+//! return raw bytes. The [`SSRIDecode`] trait turns the decoded argument bytes
+//! into Rust values and [`SSRIEncode`] turns method results back into bytes,
+//! both following the molecule encoding used throughout CKB:
+//!
+//! - Integers are little-endian with their exact width; `bool` is a single `0`/`1` byte
+//! - `[u8; 32]` is 32 raw bytes
+//! - `Bytes` and `Cow<'static, [u8]>` are passed through as raw bytes
+//! - `Vec<u8>`, `Vec<bool>`, `Vec<[u8; 32]>`, `Vec<u64>` and `Vec<u128>` are molecule `fixvec`s
+//! - `Vec<Script>` is a molecule `dynvec` of `Script`
+//! - Molecule entities like `Script` or `Transaction` use their molecule encoding
+//!   and are verified with their reader when decoded
+//! - `Option<T>` is `None` for empty bytes
+//!
+//! `#[derive(SSRIDecode)]` and `#[derive(SSRIEncode)]` implement the traits for
+//! `serde_molecule` structs.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ckb_ssri_std::codec::{decode_arg, SSRIEncode};
+//!
+//! let argv = ckb_std::env::argv();
+//! let amount: u128 = decode_arg(&argv[1])?;
+//! let res = (amount * 2).encode();
+//! ```
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::ffi::CStr;
+use ckb_std::ckb_types::{bytes::Bytes, packed, prelude::*};
+
+use crate::public_module_traits::udt::{ScriptLike, UDTPausableData};
+use crate::SSRIError;
+
+/// Types that can be decoded from the bytes of an SSRI method argument
+pub trait SSRIDecode: Sized {
+    /// Decode a value from `raw`, failing with `SSRIError::SSRIMethodsArgsInvalid`
+    /// when the bytes are malformed
+    fn decode(raw: &[u8]) -> Result<Self, SSRIError>;
+}
+
+/// Types that can be encoded as the result of an SSRI method
+pub trait SSRIEncode {
+    /// Encode the value into bytes
+    fn encode(&self) -> Vec<u8>;
+
+    /// Convert the value into the content returned by the dispatcher
+    fn into_output(self) -> Cow<'static, [u8]>
+    where
+        Self: Sized,
+    {
+        Cow::from(self.encode())
+    }
+}
+
+/// Decode a hex encoded argument into bytes
+///
+/// Unlike `ckb_std::high_level::decode_hex`, this function never panics on
+/// non-ASCII input.
+///
+/// # Errors
+///
+/// * Returns `SSRIError::SSRIMethodsArgsInvalid` if the argument is not valid hex
+pub fn decode_hex_arg(arg: &CStr) -> Result<Vec<u8>, SSRIError> {
+    fn nibble(c: u8) -> Result<u8, SSRIError> {
+        match c {
+            b'0'..=b'9' => Ok(c - b'0'),
+            b'a'..=b'f' => Ok(c - b'a' + 10),
+            b'A'..=b'F' => Ok(c - b'A' + 10),
+            _ => Err(SSRIError::SSRIMethodsArgsInvalid),
+        }
+    }
+
+    let data = arg.to_bytes();
+    if data.len() & 1 != 0 {
+        return Err(SSRIError::SSRIMethodsArgsInvalid);
+    }
+    data.chunks(2)
+        .map(|pair| Ok((nibble(pair[0])? << 4) | nibble(pair[1])?))
+        .collect()
+}
+
+/// Decode a hex encoded argument into `T`
+///
+/// # Errors
+///
+/// * Returns `SSRIError::SSRIMethodsArgsInvalid` if the argument is not valid hex
+///   or its bytes are not a valid encoding of `T`
+pub fn decode_arg<T: SSRIDecode>(arg: &CStr) -> Result<T, SSRIError> {
+    T::decode(&decode_hex_arg(arg)?)
+}
+
+/// Split a molecule `fixvec` into its items
+fn decode_fixvec(raw: &[u8], item_size: usize) -> Result<core::slice::Chunks<'_, u8>, SSRIError> {
+    if raw.len() < 4 {
+        return Err(SSRIError::SSRIMethodsArgsInvalid);
+    }
+    let len = u32::from_le_bytes(raw[0..4].try_into().unwrap()) as usize;
+    if len.checked_mul(item_size).and_then(|size| size.checked_add(4)) != Some(raw.len()) {
+        return Err(SSRIError::SSRIMethodsArgsInvalid);
+    }
+    Ok(raw[4..].chunks(item_size))
+}
+
+macro_rules! impl_decode_for_uint {
+    ($($ty:ty),*) => {
+        $(
+            impl SSRIDecode for $ty {
+                fn decode(raw: &[u8]) -> Result<Self, SSRIError> {
+                    Ok(<$ty>::from_le_bytes(
+                        raw.try_into().map_err(|_| SSRIError::SSRIMethodsArgsInvalid)?,
+                    ))
+                }
+            }
+
+            impl SSRIDecode for Vec<$ty> {
+                fn decode(raw: &[u8]) -> Result<Self, SSRIError> {
+                    Ok(decode_fixvec(raw, core::mem::size_of::<$ty>())?
+                        .map(|chunk| <$ty>::from_le_bytes(chunk.try_into().unwrap()))
+                        .collect())
+                }
+            }
+        )*
+    };
+}
+
+impl_decode_for_uint!(u16, u32, u64, u128);
+
+impl SSRIDecode for u8 {
+    fn decode(raw: &[u8]) -> Result<Self, SSRIError> {
+        match raw {
+            [value] => Ok(*value),
+            _ => Err(SSRIError::SSRIMethodsArgsInvalid),
+        }
+    }
+}
+
+impl SSRIDecode for bool {
+    fn decode(raw: &[u8]) -> Result<Self, SSRIError> {
+        match raw {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            _ => Err(SSRIError::SSRIMethodsArgsInvalid),
+        }
+    }
+}
+
+impl SSRIDecode for [u8; 32] {
+    fn decode(raw: &[u8]) -> Result<Self, SSRIError> {
+        raw.try_into().map_err(|_| SSRIError::SSRIMethodsArgsInvalid)
+    }
+}
+
+impl SSRIDecode for Vec<u8> {
+    fn decode(raw: &[u8]) -> Result<Self, SSRIError> {
+        Ok(decode_fixvec(raw, 1)?.flatten().copied().collect())
+    }
+}
+
+impl SSRIDecode for Bytes {
+    fn decode(raw: &[u8]) -> Result<Self, SSRIError> {
+        Ok(Bytes::copy_from_slice(raw))
+    }
+}
+
+impl SSRIDecode for Vec<[u8; 32]> {
+    fn decode(raw: &[u8]) -> Result<Self, SSRIError> {
+        Ok(decode_fixvec(raw, 32)?
+            .map(|chunk| chunk.try_into().unwrap())
+            .collect())
+    }
+}
+
+impl SSRIDecode for Vec<packed::Script> {
+    fn decode(raw: &[u8]) -> Result<Self, SSRIError> {
+        // A dynvec shares its layout with a molecule table
+        serde_molecule::molecule::disassemble_table(raw)
+            .map_err(|_| SSRIError::SSRIMethodsArgsInvalid)?
+            .into_iter()
+            .map(packed::Script::decode)
+            .collect()
+    }
+}
+
+impl<T: SSRIDecode> SSRIDecode for Option<T> {
+    fn decode(raw: &[u8]) -> Result<Self, SSRIError> {
+        if raw.is_empty() {
+            Ok(None)
+        } else {
+            T::decode(raw).map(Some)
+        }
+    }
+}
+
+macro_rules! impl_decode_for_entity {
+    ($($entity:ty),*) => {
+        $(
+            impl SSRIDecode for $entity {
+                fn decode(raw: &[u8]) -> Result<Self, SSRIError> {
+                    <$entity>::from_slice(raw).map_err(|_| SSRIError::SSRIMethodsArgsInvalid)
+                }
+            }
+        )*
+    };
+}
+
+impl_decode_for_entity!(
+    packed::Byte32,
+    packed::Byte32Vec,
+    packed::Bytes,
+    packed::CellOutput,
+    packed::OutPoint,
+    packed::Script,
+    packed::Transaction,
+    packed::WitnessArgs
+);
+
+macro_rules! impl_decode_for_serde_molecule {
+    ($($ty:ty),*) => {
+        $(
+            impl SSRIDecode for $ty {
+                fn decode(raw: &[u8]) -> Result<Self, SSRIError> {
+                    serde_molecule::from_slice(raw, false)
+                        .map_err(|_| SSRIError::SSRIMethodsArgsInvalid)
+                }
+            }
+        )*
+    };
+}
+
+impl_decode_for_serde_molecule!(ScriptLike, UDTPausableData);
+
+/// Encode items as a molecule `fixvec`
+fn encode_fixvec(len: usize, items: impl Iterator<Item = u8>) -> Vec<u8> {
+    u32::to_le_bytes(len as u32).into_iter().chain(items).collect()
+}
+
+macro_rules! impl_encode_for_uint {
+    ($($ty:ty),*) => {
+        $(
+            impl SSRIEncode for $ty {
+                fn encode(&self) -> Vec<u8> {
+                    self.to_le_bytes().to_vec()
+                }
+            }
+
+            impl SSRIEncode for Vec<$ty> {
+                fn encode(&self) -> Vec<u8> {
+                    encode_fixvec(self.len(), self.iter().flat_map(|v| v.to_le_bytes()))
+                }
+            }
+        )*
+    };
+}
+
+impl_encode_for_uint!(u16, u32, u64, u128);
+
+impl SSRIEncode for () {
+    fn encode(&self) -> Vec<u8> {
+        Vec::new()
+    }
+}
+
+impl SSRIEncode for u8 {
+    fn encode(&self) -> Vec<u8> {
+        [*self].to_vec()
+    }
+}
+
+impl SSRIEncode for bool {
+    fn encode(&self) -> Vec<u8> {
+        [*self as u8].to_vec()
+    }
+}
+
+impl SSRIEncode for [u8; 32] {
+    fn encode(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+impl SSRIEncode for Vec<u8> {
+    fn encode(&self) -> Vec<u8> {
+        encode_fixvec(self.len(), self.iter().copied())
+    }
+}
+
+impl SSRIEncode for Vec<bool> {
+    fn encode(&self) -> Vec<u8> {
+        encode_fixvec(self.len(), self.iter().map(|v| *v as u8))
+    }
+}
+
+impl SSRIEncode for Vec<[u8; 32]> {
+    fn encode(&self) -> Vec<u8> {
+        encode_fixvec(self.len(), self.iter().flatten().copied())
+    }
+}
+
+impl SSRIEncode for Vec<packed::Script> {
+    fn encode(&self) -> Vec<u8> {
+        serde_molecule::molecule::assemble_table(
+            &self.iter().map(|script| script.as_slice().to_vec()).collect::<Vec<_>>(),
+        )
+    }
+}
+
+impl SSRIEncode for Bytes {
+    fn encode(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+impl SSRIEncode for &'static [u8] {
+    fn encode(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+
+    fn into_output(self) -> Cow<'static, [u8]> {
+        Cow::from(self)
+    }
+}
+
+impl SSRIEncode for Cow<'static, [u8]> {
+    fn encode(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+
+    fn into_output(self) -> Cow<'static, [u8]> {
+        self
+    }
+}
+
+impl<T: SSRIEncode> SSRIEncode for Option<T> {
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            Some(value) => value.encode(),
+            None => Vec::new(),
+        }
+    }
+}
+
+macro_rules! impl_encode_for_entity {
+    ($($entity:ty),*) => {
+        $(
+            impl SSRIEncode for $entity {
+                fn encode(&self) -> Vec<u8> {
+                    self.as_slice().to_vec()
+                }
+            }
+        )*
+    };
+}
+
+impl_encode_for_entity!(
+    packed::Byte32,
+    packed::Byte32Vec,
+    packed::Bytes,
+    packed::CellOutput,
+    packed::OutPoint,
+    packed::Script,
+    packed::Transaction,
+    packed::WitnessArgs
+);
+
+macro_rules! impl_encode_for_serde_molecule {
+    ($($ty:ty),*) => {
+        $(
+            impl SSRIEncode for $ty {
+                fn encode(&self) -> Vec<u8> {
+                    serde_molecule::to_vec(self, false)
+                        .expect("serde_molecule types should always serialize")
+                }
+            }
+        )*
+    };
+}
+
+impl_encode_for_serde_molecule!(ScriptLike, UDTPausableData);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn round_trip<T: SSRIEncode + SSRIDecode + PartialEq + core::fmt::Debug>(value: T) {
+        assert_eq!(T::decode(&value.encode()).unwrap(), value);
+    }
+
+    fn rejects<T: SSRIDecode>(raw: &[u8]) -> bool {
+        matches!(T::decode(raw), Err(SSRIError::SSRIMethodsArgsInvalid))
+    }
+
+    fn script(args: &[u8]) -> packed::Script {
+        packed::Script::new_builder()
+            .code_hash([7u8; 32].pack())
+            .hash_type(1u8.into())
+            .args(args.pack())
+            .build()
+    }
+
+    #[test]
+    fn round_trips_integers() {
+        round_trip(0xabu8);
+        round_trip(0xabcdu16);
+        round_trip(u32::MAX);
+        round_trip(u64::MAX - 1);
+        round_trip(u128::MAX / 3);
+        round_trip(true);
+        round_trip(false);
+        round_trip([9u8; 32]);
+        assert_eq!(258u16.encode(), [2, 1]);
+    }
+
+    #[test]
+    fn rejects_integers_of_the_wrong_width() {
+        assert!(rejects::<u8>(&[]));
+        assert!(rejects::<u8>(&[1, 2]));
+        assert!(rejects::<u64>(&[0; 7]));
+        assert!(rejects::<u128>(&[0; 17]));
+        assert!(rejects::<bool>(&[2]));
+        assert!(rejects::<[u8; 32]>(&[0; 31]));
+    }
+
+    #[test]
+    fn round_trips_fixvecs() {
+        round_trip(vec![1u8, 2, 3]);
+        round_trip(vec![1u64, u64::MAX]);
+        round_trip(vec![u128::MAX]);
+        round_trip(Vec::<u32>::new());
+        round_trip(vec![[1u8; 32], [2u8; 32]]);
+        assert_eq!(vec![1u16, 2].encode(), [2, 0, 0, 0, 1, 0, 2, 0]);
+        assert_eq!(vec![true, false].encode(), [2, 0, 0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn checks_fixvec_lengths() {
+        assert!(rejects::<Vec<u8>>(&[]));
+        assert!(rejects::<Vec<u8>>(&[1, 0, 0]));
+        assert!(rejects::<Vec<u8>>(&[2, 0, 0, 0, 1]));
+        assert!(rejects::<Vec<u8>>(&[1, 0, 0, 0, 1, 2]));
+        assert!(rejects::<Vec<u64>>(&[1, 0, 0, 0, 1, 2, 3, 4]));
+        assert!(rejects::<Vec<u128>>(&[0xff, 0xff, 0xff, 0xff]));
+        assert!(rejects::<Vec<[u8; 32]>>(&[1, 0, 0, 0]));
+    }
+
+    #[test]
+    fn round_trips_script_dynvecs() {
+        round_trip(vec![script(&[]), script(&[1, 2, 3])]);
+        round_trip(Vec::<packed::Script>::new());
+        let raw = vec![script(&[1])].encode();
+        assert!(rejects::<Vec<packed::Script>>(&raw[..raw.len() - 1]));
+        assert!(rejects::<Vec<packed::Script>>(&[1, 0, 0]));
+        // A dynvec of a valid table which is not a `Script`
+        let not_a_script = serde_molecule::molecule::assemble_table(&[vec![1, 2, 3]]);
+        assert!(rejects::<Vec<packed::Script>>(&not_a_script));
+    }
+
+    #[test]
+    fn round_trips_entities_and_bytes() {
+        round_trip(script(&[4, 5]));
+        round_trip(Bytes::from_static(b"raw bytes"));
+        assert!(rejects::<packed::Script>(&script(&[4, 5]).as_slice()[1..]));
+        assert_eq!(b"raw".as_slice().into_output().as_ref(), b"raw");
+    }
+
+    #[test]
+    fn decodes_empty_options_as_none() {
+        assert_eq!(Option::<u64>::decode(&[]).unwrap(), None);
+        assert_eq!(Option::<packed::Script>::decode(&[]).unwrap(), None);
+        round_trip(Some(5u64));
+        round_trip(Option::<u64>::None);
+        assert!(rejects::<Option<u64>>(&[1]));
+    }
+
+    #[test]
+    fn decodes_hex_arguments() {
+        assert!(decode_hex_arg(c"").unwrap().is_empty());
+        assert_eq!(decode_hex_arg(c"00aBFf").unwrap(), [0x00, 0xab, 0xff]);
+        assert_eq!(decode_arg::<u16>(c"0201").unwrap(), 258);
+        assert!(decode_arg::<u16>(c"02").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_hex_arguments() {
+        for arg in [c"0", c"abc", c"0x00", c"zz", c"0g", c"00 1", c"\xc3\xa9\xc3\xa9"] {
+            assert!(
+                matches!(decode_hex_arg(arg), Err(SSRIError::SSRIMethodsArgsInvalid)),
+                "{:?} must be rejected",
+                arg
+            );
+        }
+    }
+}
@@ -1,4 +1,4 @@
-#![no_std]
+#![cfg_attr(not(feature = "native-simulator"), no_std)]
 //! # CKB SSRI std
 //! 
 //! Utils for implementing SSRI-compliant smart contracts on the Nervos CKB blockchain.
@@ -15,6 +15,17 @@
 //! - **Utility Functions**: Helper functions for SSRI-VM syscalls and data handling
 //! - **Procedural Macros**: Simplify contract development with automatic SSRI method generation
 //! - **No Standard Library**: Designed for the constrained smart contract environment
+//! - **Native Simulator**: The `native-simulator` feature serves the SSRI syscalls from an
+//!   in-memory chain state for native unit tests
+//! - **Mock Syscalls**: The `mock` feature provides `MockSyscalls`, a syscall backend
+//!   with programmable responses for off-chain tests of the `*_with` helpers
+//! - **Method Info**: The `method-info` feature serves the names and signatures of the
+//!   methods through `SSRI.get_method_info`
+//! - **Interface Manifest**: The `manifest` feature writes the methods registered by the
+//!   macros to `$OUT_DIR/ssri-manifest.json` at build time
+//! - **Experimental Syscalls**: The `experimental-syscalls` feature enables the
+//!   SSRI syscalls looking cells up by lock script and loading headers, which no
+//!   SSRI-VM implements yet; their numbers may change
 //!
 //! ## Usage
 //!
@@ -27,16 +38,42 @@
 //! ## Example
 //!
 //! ```rust,no_run
-//! use ckb_ssri_std::prelude::*;
-//! use ckb_ssri_std::public_module_traits::udt::UDT;
+//! use ckb_ssri_std::public_module_traits::udt::{UDTError, UDT};
+//! use ckb_ssri_std::SSRIError;
+//! use ckb_std::ckb_types::{bytes::Bytes, packed::{Script, Transaction}};
 //! 
 //! // Implement a basic UDT (User-Defined Token)
 //! #[derive(Default)]
 //! struct MyToken;
 //! 
 //! impl UDT for MyToken {
-//!     type Error = ();
-//!     // ... implement required methods
+//!     type Error = UDTError;
+//!
+//!     fn name() -> Result<Bytes, Self::Error> {
+//!         Ok(Bytes::from_static(b"My Token"))
+//!     }
+//!     fn symbol() -> Result<Bytes, Self::Error> {
+//!         Ok(Bytes::from_static(b"MTK"))
+//!     }
+//!     fn decimals() -> Result<u8, Self::Error> {
+//!         Ok(8)
+//!     }
+//!     fn icon() -> Result<Bytes, Self::Error> {
+//!         Ok(Bytes::new())
+//!     }
+//!     // ... implement the other required methods
+//! #   fn transfer(_: Option<Transaction>, _: Vec<Script>, _: Vec<u128>) -> Result<Transaction, Self::Error> {
+//! #       Err(UDTError::SSRI(SSRIError::SSRIMethodsNotImplemented))
+//! #   }
+//! #   fn verify_transfer() -> Result<(), Self::Error> {
+//! #       Err(UDTError::SSRI(SSRIError::SSRIMethodsNotImplemented))
+//! #   }
+//! #   fn mint(_: Option<Transaction>, _: Vec<Script>, _: Vec<u128>) -> Result<Transaction, Self::Error> {
+//! #       Err(UDTError::SSRI(SSRIError::SSRIMethodsNotImplemented))
+//! #   }
+//! #   fn verify_mint() -> Result<(), Self::Error> {
+//! #       Err(UDTError::SSRI(SSRIError::SSRIMethodsNotImplemented))
+//! #   }
 //! }
 //! ```
 
@@ -44,42 +81,239 @@ pub mod public_module_traits;
 pub mod prelude;
 pub mod utils;
 pub mod macros;
+pub mod codec;
 
 // Re-export proc macros at crate root for convenience
 pub use macros::*;
 
 extern crate alloc;
 
-// macro_rules! ssri_entry {
-//     ( $( $module:path ),* $(,)? ) => {
-//         pub fn unified_dispatch(namespace_and_function: &str, args: Vec<&str>) -> Result<String, crate::error::DispatchError> {
-//             $(
-//                 let argv = ckb_std::env::argv();
-//                 if argv.is_empty() {
-//                     return fallback::fallback().map(|_| ());
-//                 }
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_molecule;
+}
+
+/// Generate the unified entry of an SSRI-compliant script
+///
+/// Routes an SSRI call across the listed `#[ssri_module]` modules and produces:
+///
+/// - `ssri_dispatch(argv)`: the dispatcher serving the methods of all modules,
+///   with single `SSRI.get_methods`, `SSRI.get_method_levels` and
+///   `SSRI.get_method_info` listings covering all of them
+/// - `program_entry() -> i8`: the script entry which calls `fallback` when
+///   `argv` is empty (regular on-chain validation), verifies the VM version
+///   otherwise, and returns the result of the method with `set_content`
+///
+/// Method paths colliding across modules are rejected at compile time, with an
+/// error naming the colliding methods.
+///
+/// `version` is the `major.minor.patch` version of the interface of the
+/// script reported by `SSRI.version`, `0.0.0` when omitted; it replaces the
+/// versions declared by the modules and is exposed as `SSRI_CONTRACT_VERSION`.
+///
+/// `error` must be a fieldless `#[repr(i8)]` enum implementing `From<SysError>`,
+/// `From<SSRIError>` and `From` the error types of all modules. `fallback` is a
+/// function returning `Result<(), error>`.
+///
+/// # Example
+///
+/// ```ignore
+/// ckb_std::entry!(program_entry);
+/// ckb_std::default_alloc!();
+///
+/// ckb_ssri_std::ssri_entry! {
+///     modules: [udt, udt_pausable, modules::custom],
+///     version: "1.2.0",
+///     fallback: fallback::fallback,
+///     error: Error,
+/// }
+/// ```
+///
+/// Modules exposing the same method fail the build:
+///
+/// ```compile_fail,E0080
+/// # extern crate alloc;
+/// use ckb_ssri_std::{ssri_entry, ssri_module, SSRIError};
+/// # use ckb_std::error::SysError;
+/// #
+/// # #[repr(i8)]
+/// # pub enum Error {
+/// #     Sys = 1,
+/// #     SSRI,
+/// # }
+/// #
+/// # impl From<SysError> for Error {
+/// #     fn from(_: SysError) -> Self {
+/// #         Error::Sys
+/// #     }
+/// # }
+/// #
+/// # impl From<SSRIError> for Error {
+/// #     fn from(_: SSRIError) -> Self {
+/// #         Error::SSRI
+/// #     }
+/// # }
+///
+/// #[ssri_module(name = "UDT")]
+/// mod udt {
+///     use super::Error;
+///
+///     #[ssri_method]
+///     pub fn decimals() -> Result<u8, Error> {
+///         Ok(8)
+///     }
+/// }
+///
+/// #[ssri_module(name = "UDT")]
+/// mod udt_metadata {
+///     use super::Error;
+///
+///     #[ssri_method]
+///     pub fn decimals() -> Result<u8, Error> {
+///         Ok(6)
+///     }
+/// }
+///
+/// fn fallback() -> Result<(), Error> {
+///     Ok(())
+/// }
+///
+/// ssri_entry! {
+///     modules: [udt, udt_metadata],
+///     fallback: fallback,
+///     error: Error,
+/// }
+/// # fn main() {}
+/// ```
+#[macro_export]
+macro_rules! ssri_entry {
+    (
+        modules: [$($($module:ident)::+),+ $(,)?],
+        $(version: $version:expr,)?
+        fallback: $fallback:expr,
+        error: $error:ty $(,)?
+    ) => {
+        const _: () = {
+            let names: &[&[&str]] = &[
+                &$crate::utils::dispatch::RESERVED_METHOD_NAMES,
+                $(&$($module)::+::SSRI_METHOD_NAMES,)+
+            ];
+            if let Some(((list, index), (other_list, other_index))) =
+                $crate::utils::dispatch::find_duplicate_path(&[
+                    &$crate::utils::dispatch::RESERVED_METHOD_PATHS,
+                    $(&$($module)::+::SSRI_METHOD_PATHS,)+
+                ])
+            {
+                let message = $crate::utils::dispatch::CollisionMessage::new(
+                    names[list][index],
+                    names[other_list][other_index],
+                );
+                panic!("{}", message.as_str());
+            }
+        };
+
+        /// Version of the interface of this script reported by `SSRI.version`
+        pub const SSRI_CONTRACT_VERSION: $crate::utils::version::ContractVersion = {
+            #[allow(unused_mut)]
+            let mut version = $crate::utils::version::ContractVersion::new(0, 0, 0);
+            $(version = $crate::utils::version::ContractVersion::parse($version);)?
+            version
+        };
 
-//                 if vm_version() != u64::MAX {
-//                     return Err(Error::InvalidVmVersion);
-//                 }
+        /// Dispatch an SSRI call across the methods of all SSRI modules
+        pub fn ssri_dispatch(
+            argv: &[ckb_std::env::Arg],
+        ) -> Result<alloc::borrow::Cow<'static, [u8]>, $error> {
+            const METHOD_COUNT: usize = $crate::utils::dispatch::RESERVED_METHOD_PATHS.len()
+                $(+ $($module)::+::SSRI_METHOD_PATHS.len())+;
+            const METHOD_PATHS: [u64; METHOD_COUNT] = $crate::utils::dispatch::concat_lists(
+                &[
+                    &$crate::utils::dispatch::RESERVED_METHOD_PATHS,
+                    $(&$($module)::+::SSRI_METHOD_PATHS,)+
+                ],
+                0,
+            );
+            const METHOD_LEVELS: [u8; METHOD_COUNT] = $crate::utils::dispatch::concat_lists(
+                &[
+                    &$crate::utils::dispatch::RESERVED_METHOD_LEVELS,
+                    $(&$($module)::+::SSRI_METHOD_LEVELS,)+
+                ],
+                0,
+            );
 
-//                 set_content(&res)?;
-//                 if $module::EXPOSED_FUNCTIONS.contains(&namespace_and_function) {
-//                     return $module::dispatch_function(namespace_and_function, args);
-//                 }
-//             )*
-//             Err(crate::error::DispatchError::FunctionNotFound)
-//         }
+            let path = $crate::utils::dispatch::decode_method_path(argv)
+                .map_err(<$error>::from)?;
+            if let Some(res) = $crate::utils::dispatch::dispatch_reserved(
+                path,
+                argv,
+                &METHOD_PATHS,
+                &METHOD_LEVELS,
+                SSRI_CONTRACT_VERSION,
+            ) {
+                return res.map_err(<$error>::from);
+            }
+            if let Some(res) = $crate::__dispatch_method_info!(
+                path,
+                argv,
+                METHOD_PATHS,
+                METHOD_COUNT,
+                [$($($module)::+),+]
+            ) {
+                return res.map_err(<$error>::from);
+            }
+            $(
+                if let Some(res) = $($module)::+::ssri_call(path, argv) {
+                    return res.map_err(<$error>::from);
+                }
+            )+
+            Err(<$error>::from($crate::SSRIError::SSRIMethodsNotFound))
+        }
 
-//         pub fn get_methods() -> Vec<&'static str> {
-//             let mut methods = Vec::new();
-//             $(
-//                 methods.extend_from_slice($module::EXPOSED_FUNCTIONS);
-//             )*
-//             methods
-//         }
-//     };
-// }
+        pub fn program_entry() -> i8 {
+            fn entry() -> Result<(), $error> {
+                if $crate::utils::should_fallback()? {
+                    return $fallback();
+                }
+                let res = ssri_dispatch($crate::utils::syscalls::argv())?;
+                $crate::utils::syscalls::set_content(&res)?;
+                Ok(())
+            }
+
+            match entry() {
+                Ok(()) => 0,
+                Err(err) => err as i8,
+            }
+        }
+    };
+}
+
+/// Serve `SSRI.get_method_info` across the modules of `ssri_entry!`
+#[cfg(feature = "method-info")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dispatch_method_info {
+    ($path:expr, $argv:expr, $methods:ident, $count:ident, [$($($module:ident)::+),+]) => {{
+        const METHOD_INFOS: [$crate::utils::dispatch::MethodInfo; $count] =
+            $crate::utils::dispatch::concat_lists(
+                &[
+                    &$crate::utils::dispatch::RESERVED_METHOD_INFOS,
+                    $(&$($module)::+::SSRI_METHOD_INFOS,)+
+                ],
+                $crate::utils::dispatch::RESERVED_METHOD_INFOS[0],
+            );
+        $crate::utils::dispatch::dispatch_method_info($path, $argv, &$methods, &METHOD_INFOS)
+    }};
+}
+
+/// Without the `method-info` feature, `SSRI.get_method_info` is not served
+#[cfg(not(feature = "method-info"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dispatch_method_info {
+    ($($tokens:tt)*) => {
+        None::<Result<alloc::borrow::Cow<'static, [u8]>, $crate::SSRIError>>
+    };
+}
 
 #[repr(i8)]
 #[derive(Debug)]
@@ -89,6 +323,11 @@ extern crate alloc;
 /// SSRI methods. These errors help identify issues with method discovery,
 /// argument validation, implementation status, and environment compatibility.
 ///
+/// `From<SSRIError>` is the conversion the generated dispatchers rely on to
+/// report these errors with the error type of a contract: implement it for
+/// the contract error enum to use it as `error` in `ssri_methods!` and
+/// `ssri_entry!`, or as the error type of `#[ssri_method]` functions.
+///
 /// # Examples
 ///
 /// ```rust
@@ -98,6 +337,27 @@ extern crate alloc;
 ///     // Method implementation missing
 ///     Err(SSRIError::SSRIMethodsNotImplemented)
 /// }
+///
+/// #[repr(i8)]
+/// enum Error {
+///     SSRIMethodsNotFound = 1,
+///     SSRIMethodsArgsInvalid,
+///     SSRIMethodsNotImplemented,
+///     SSRIMethodRequireHigherLevel,
+///     InvalidVmVersion,
+/// }
+///
+/// impl From<SSRIError> for Error {
+///     fn from(err: SSRIError) -> Self {
+///         match err {
+///             SSRIError::SSRIMethodsNotFound => Error::SSRIMethodsNotFound,
+///             SSRIError::SSRIMethodsArgsInvalid => Error::SSRIMethodsArgsInvalid,
+///             SSRIError::SSRIMethodsNotImplemented => Error::SSRIMethodsNotImplemented,
+///             SSRIError::SSRIMethodRequireHigherLevel => Error::SSRIMethodRequireHigherLevel,
+///             SSRIError::InvalidVmVersion => Error::InvalidVmVersion,
+///         }
+///     }
+/// }
 /// ```
 pub enum SSRIError {
     /// The requested SSRI method was not found in the contract
@@ -6,6 +6,7 @@
 //!
 //! # Available Macros
 //!
+//! - `ssri_methods`: Dispatches an SSRI call over a list of `"<path>" => <body>` arms
 //! - `ssri_module`: Marks a module as an SSRI-compliant module
 //! - `ssri_method`: Marks a function as an exposed SSRI method
 //!
@@ -14,13 +15,162 @@
 //! ```ignore
 //! use ckb_ssri_std::macros::*;
 //!
-//! #[ssri_module]
+//! #[ssri_module(name = "MyContract")]
 //! mod my_contract {
+//!     use super::*;
+//!
+//!     // Exposed as `MyContract.my_method`
 //!     #[ssri_method]
-//!     fn my_method() -> Result<(), Error> {
+//!     pub fn my_method() -> Result<Cow<'static, [u8]>, Error> {
 //!         // Implementation
 //!     }
 //! }
+//!
+//! let res = my_contract::ssri_dispatch(ckb_std::env::argv())?;
+//! ```
+//!
+//! # Rejected methods
+//!
+//! `#[ssri_module]` fails the build for `#[ssri_method]` functions that are
+//! not `pub`:
+//!
+//! ```compile_fail
+//! # extern crate alloc;
+//! use ckb_ssri_std::{ssri_module, SSRIError};
+//!
+//! #[ssri_module]
+//! mod token {
+//!     use super::*;
+//!
+//!     #[ssri_method]
+//!     fn decimals() -> Result<u8, SSRIError> {
+//!         Ok(8)
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! that do not return a `Result`:
+//!
+//! ```compile_fail
+//! # extern crate alloc;
+//! use ckb_ssri_std::ssri_module;
+//!
+//! #[ssri_module]
+//! mod token {
+//!     #[ssri_method]
+//!     pub fn decimals() -> u8 {
+//!         8
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! that declare an argument count, which is the parameter count of
+//! `#[ssri_method]` functions and of typed `ssri_methods!` closures:
+//!
+//! ```compile_fail
+//! # extern crate alloc;
+//! use ckb_ssri_std::{ssri_module, SSRIError};
+//!
+//! #[ssri_module]
+//! mod token {
+//!     use super::*;
+//!
+//!     #[ssri_method(args = 1)]
+//!     pub fn decimals() -> Result<u8, SSRIError> {
+//!         Ok(8)
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! or whose error type differs from the other methods of the module:
+//!
+//! ```compile_fail
+//! # extern crate alloc;
+//! use ckb_ssri_std::{ssri_module, SSRIError};
+//!
+//! pub struct Error;
+//!
+//! impl From<SSRIError> for Error {
+//!     fn from(_: SSRIError) -> Self {
+//!         Error
+//!     }
+//! }
+//!
+//! #[ssri_module]
+//! mod token {
+//!     use super::*;
+//!
+//!     #[ssri_method]
+//!     pub fn decimals() -> Result<u8, SSRIError> {
+//!         Ok(8)
+//!     }
+//!
+//!     #[ssri_method]
+//!     pub fn symbol() -> Result<u8, Error> {
+//!         Ok(0)
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! Both `ssri_methods!` and `#[ssri_module]` reject methods sharing a name or
+//! a method path with each other:
+//!
+//! ```compile_fail
+//! # extern crate alloc;
+//! use alloc::borrow::Cow;
+//! use ckb_ssri_std::{ssri_methods, SSRIError};
+//! use ckb_std::env::Arg;
+//!
+//! fn dispatch(argv: &[Arg]) -> Result<Cow<'static, [u8]>, SSRIError> {
+//!     ssri_methods!(
+//!         argv: argv,
+//!         "UDT.decimals" => Ok(8u8),
+//!         "UDT.decimals" => Ok(6u8),
+//!     )
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! or with a reserved `SSRI.*` method:
+//!
+//! ```compile_fail
+//! # extern crate alloc;
+//! use ckb_ssri_std::{ssri_module, SSRIError};
+//!
+//! #[ssri_module(name = "SSRI")]
+//! mod ssri {
+//!     use super::*;
+//!
+//!     #[ssri_method]
+//!     pub fn version() -> Result<u8, SSRIError> {
+//!         Ok(1)
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! Contract versions are parsed by
+//! [`ContractVersion::parse`](crate::utils::version::ContractVersion::parse)
+//! at compile time, so a malformed version fails the build as well:
+//!
+//! ```compile_fail,E0080
+//! # extern crate alloc;
+//! use ckb_ssri_std::{ssri_module, SSRIError};
+//!
+//! #[ssri_module(version = "1.2")]
+//! mod token {
+//!     use super::*;
+//!
+//!     #[ssri_method]
+//!     pub fn decimals() -> Result<u8, SSRIError> {
+//!         Ok(8)
+//!     }
+//! }
+//! # fn main() {}
 //! ```
 
 pub use ckb_ssri_std_proc_macro::*;
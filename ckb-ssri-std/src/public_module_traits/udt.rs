@@ -1,12 +1,16 @@
 use ckb_std::ckb_types::{
     bytes::Bytes,
-    packed::{Byte32Vec, Script, Transaction},
+    packed::{Byte, Byte32Vec, Script, Transaction},
+    prelude::*,
 };
+use ckb_std::error::SysError;
 extern crate alloc;
 
 use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
+use crate::SSRIError;
+
 /// User-Defined Token (UDT) trait for implementing custom tokens on CKB
 ///
 /// This trait defines the standard interface for implementing fungible tokens
@@ -15,18 +19,26 @@ use serde::{Deserialize, Serialize};
 /// # Implementation Notes
 ///
 /// - All amounts are represented as u128 in convention
-/// - Methods that modify state return a Transaction that must be committed
-/// - Verification methods are separate from state-changing methods
+/// - Methods that modify state return a Transaction that must be committed;
+///   `utils::tx_builder::UDTTransactionBuilder` appends the UDT outputs and
+///   cell dep to the partial transaction they receive
+/// - Verification methods are separate from state-changing methods; they can
+///   build on the amount totals of [`crate::utils::amount`], such as
+///   [`verify_amount_conservation`](crate::utils::amount::verify_amount_conservation)
+/// - `balance`, `total_supply`, `cap`, `burn` and `verify_burn` are optional:
+///   by default they fail with `SSRIError::SSRIMethodsNotImplemented`. They
+///   require `Self::Error: From<SSRIError>`, so they can only be called,
+///   overridden or not, on implementations whose error converts from it
 ///
 /// # Example
 ///
-/// ```rust,no_run
-/// use ckb_ssri_std::public_module_traits::udt::UDT;
+/// ```rust,ignore
+/// use ckb_ssri_std::public_module_traits::udt::{UDTError, UDT};
 ///
 /// struct MyToken;
 ///
 /// impl UDT for MyToken {
-///     type Error = ();
+///     type Error = UDTError;
 ///     
 ///     fn balance() -> Result<u128, Self::Error> {
 ///         // Implementation
@@ -53,14 +65,93 @@ pub trait UDT {
         to_amount_vec: Vec<u128>,
     ) -> Result<Transaction, Self::Error>;
     fn verify_mint() -> Result<(), Self::Error>;
+    /// Amount held by the cell the script runs on
+    fn balance() -> Result<u128, Self::Error>
+    where
+        Self::Error: From<SSRIError>,
+    {
+        Err(SSRIError::SSRIMethodsNotImplemented.into())
+    }
+    /// Amount of the token in circulation
+    fn total_supply() -> Result<u128, Self::Error>
+    where
+        Self::Error: From<SSRIError>,
+    {
+        Err(SSRIError::SSRIMethodsNotImplemented.into())
+    }
+    /// Maximum amount of the token that can ever be minted
+    fn cap() -> Result<u128, Self::Error>
+    where
+        Self::Error: From<SSRIError>,
+    {
+        Err(SSRIError::SSRIMethodsNotImplemented.into())
+    }
+    /// Burn `amount_vec[i]` from the cells locked by `lock_vec[i]`, completing
+    /// `tx` if given
+    fn burn(
+        _tx: Option<Transaction>,
+        _lock_vec: Vec<Script>,
+        _amount_vec: Vec<u128>,
+    ) -> Result<Transaction, Self::Error>
+    where
+        Self::Error: From<SSRIError>,
+    {
+        Err(SSRIError::SSRIMethodsNotImplemented.into())
+    }
+    /// Verify a burn on-chain, failing with `UDTError::NoBurnPermission` or an
+    /// equivalent error if it is not allowed
+    fn verify_burn() -> Result<(), Self::Error>
+    where
+        Self::Error: From<SSRIError>,
+    {
+        Err(SSRIError::SSRIMethodsNotImplemented.into())
+    }
 }
 pub const UDT_LEN: usize = 16;
+
+/// Errors of the UDT helpers
+///
+/// New variants may be added as the helpers grow, so matches on it need a
+/// wildcard arm.
+#[derive(Debug)]
+#[non_exhaustive]
 pub enum UDTError {
     InsufficientBalance,
     NoMintPermission,
     NoBurnPermission,
+    SSRI(SSRIError),
+    Sys(SysError),
+}
+
+impl From<SSRIError> for UDTError {
+    fn from(err: SSRIError) -> Self {
+        UDTError::SSRI(err)
+    }
+}
+
+impl From<SysError> for UDTError {
+    fn from(err: SysError) -> Self {
+        UDTError::Sys(err)
+    }
 }
 
+/// Pausable extension of [`UDT`]
+///
+/// Implementations usually answer `is_paused` and `enumerate_paused` from the
+/// pause data of the script with the helpers of
+/// [`crate::utils::pause_list`], such as
+/// [`is_paused`](crate::utils::pause_list::is_paused) and
+/// [`enumerate_paused`](crate::utils::pause_list::enumerate_paused), which
+/// follow and merge the whole chain.
+///
+/// Pausing without lock hashes pauses every lock. The pause list records it as
+/// the all-zero entry
+/// [`GLOBAL_PAUSE_LOCK_HASH`](crate::utils::pause_list::GLOBAL_PAUSE_LOCK_HASH),
+/// which no script hashes to: `pause` should store it for an empty
+/// `lock_hashes` and `unpause` remove it. While it is stored, `is_paused`
+/// should report every lock as paused, and `enumerate_paused` should not list
+/// it; the helpers do both.
+#[allow(clippy::ptr_arg)]
 pub trait UDTPausable: UDT {
     /* NOTE: Pausing/Unpause without lock hashes should take effect on the global level */
     fn pause(
@@ -75,22 +166,223 @@ pub trait UDTPausable: UDT {
     fn enumerate_paused(offset: u64, limit: u64) -> Result<Byte32Vec, Self::Error>;
 }
 
+/// One link of the pause list of a token, chained to the next link through the
+/// type script of the cell holding it
+///
+/// See [`crate::utils::pause_list::PauseList`] to load and merge a whole chain.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct UDTPausableData {
     pub pause_list: Vec<[u8; 32]>,
     pub next_type_script: Option<ScriptLike>
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// `packed::Script` as a serde_molecule struct, for use in SSRI arguments and
+/// cell data
+///
+/// It converts to `packed::Script` with `TryFrom`, which checks the hash type,
+/// and from it with `From`:
+///
+/// ```
+/// use ckb_ssri_std::public_module_traits::udt::{HashType, ScriptLike};
+/// use ckb_std::ckb_types::{packed::Script, prelude::*};
+///
+/// let script_like = ScriptLike::new([1; 32], HashType::Type, vec![2; 20]);
+/// let script = Script::try_from(&script_like).unwrap();
+/// assert_eq!(script.hash_type(), HashType::Type.into());
+/// assert_eq!(ScriptLike::from(&script), script_like);
+/// assert_eq!(
+///     script_like.calc_script_hash().ok(),
+///     Some(script.calc_script_hash().unpack())
+/// );
+///
+/// let invalid = ScriptLike { hash_type: 3, ..script_like };
+/// assert!(Script::try_from(&invalid).is_err());
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct ScriptLike {
     pub code_hash: [u8; 32],
     pub hash_type: u8,
     pub args: Vec<u8>,
 }
+
+impl ScriptLike {
+    pub fn new(code_hash: [u8; 32], hash_type: HashType, args: Vec<u8>) -> Self {
+        ScriptLike {
+            code_hash,
+            hash_type: hash_type.into(),
+            args,
+        }
+    }
+
+    /// The hash type as a [`HashType`]
+    ///
+    /// # Errors
+    ///
+    /// * Returns `SSRIError::SSRIMethodsArgsInvalid` if `hash_type` is not a
+    ///   valid hash type
+    pub fn typed_hash_type(&self) -> Result<HashType, SSRIError> {
+        HashType::try_from(self.hash_type)
+    }
+
+    /// Hash of the script, as referenced by lock and type script hashes
+    ///
+    /// # Errors
+    ///
+    /// * Returns `SSRIError::SSRIMethodsArgsInvalid` if `hash_type` is not a
+    ///   valid hash type
+    pub fn calc_script_hash(&self) -> Result<[u8; 32], SSRIError> {
+        Ok(Script::try_from(self)?.calc_script_hash().unpack())
+    }
+}
+
+impl From<&Script> for ScriptLike {
+    fn from(script: &Script) -> Self {
+        ScriptLike {
+            code_hash: script.code_hash().unpack(),
+            hash_type: script.hash_type().into(),
+            args: script.args().raw_data().to_vec(),
+        }
+    }
+}
+
+impl From<Script> for ScriptLike {
+    fn from(script: Script) -> Self {
+        ScriptLike::from(&script)
+    }
+}
+
+impl TryFrom<&ScriptLike> for Script {
+    type Error = SSRIError;
+
+    fn try_from(script: &ScriptLike) -> Result<Self, Self::Error> {
+        Ok(Script::new_builder()
+            .code_hash(script.code_hash.pack())
+            .hash_type(script.typed_hash_type()?.into())
+            .args(script.args.pack())
+            .build())
+    }
+}
+
+impl TryFrom<ScriptLike> for Script {
+    type Error = SSRIError;
+
+    fn try_from(script: ScriptLike) -> Result<Self, Self::Error> {
+        Script::try_from(&script)
+    }
+}
+
+/// Hash type of a script, selecting how its `code_hash` matches the code cell
+/// and which VM version runs it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum HashType {
+    /// `code_hash` is the data hash of the code cell, run with VM version 0
+    Data = 0,
+    /// `code_hash` is the type script hash of the code cell
+    Type = 1,
+    /// `code_hash` is the data hash of the code cell, run with VM version 1
+    Data1 = 2,
+    /// `code_hash` is the data hash of the code cell, run with VM version 2
+    Data2 = 4,
+}
+
+impl TryFrom<u8> for HashType {
+    type Error = SSRIError;
+
+    fn try_from(hash_type: u8) -> Result<Self, Self::Error> {
+        match hash_type {
+            0 => Ok(HashType::Data),
+            1 => Ok(HashType::Type),
+            2 => Ok(HashType::Data1),
+            4 => Ok(HashType::Data2),
+            _ => Err(SSRIError::SSRIMethodsArgsInvalid),
+        }
+    }
+}
+
+impl From<HashType> for u8 {
+    fn from(hash_type: HashType) -> Self {
+        hash_type as u8
+    }
+}
+
+impl From<HashType> for Byte {
+    fn from(hash_type: HashType) -> Self {
+        Byte::new(hash_type.into())
+    }
+}
+
+#[derive(Debug)]
 pub enum UDTPausableError {
     NoPausePermission,
     NoUnpausePermission,
     AbortedFromPause,
     IncompletePauseList,
     CyclicPauseList,
+    SSRI(SSRIError),
+}
+
+impl From<SSRIError> for UDTPausableError {
+    fn from(err: SSRIError) -> Self {
+        UDTPausableError::SSRI(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn converts_every_hash_type() {
+        for (byte, hash_type) in [
+            (0, HashType::Data),
+            (1, HashType::Type),
+            (2, HashType::Data1),
+            (4, HashType::Data2),
+        ] {
+            assert_eq!(HashType::try_from(byte).ok(), Some(hash_type));
+            assert_eq!(u8::from(hash_type), byte);
+            assert_eq!(Byte::from(hash_type), Byte::new(byte));
+
+            let script_like = ScriptLike::new([1; 32], hash_type, vec![2; 20]);
+            assert_eq!(script_like.typed_hash_type().ok(), Some(hash_type));
+            let script = Script::try_from(&script_like).unwrap();
+            assert_eq!(script.hash_type(), Byte::new(byte));
+            assert_eq!(ScriptLike::from(script), script_like);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_hash_types() {
+        for byte in [3, 5, 0x80, u8::MAX] {
+            assert!(matches!(HashType::try_from(byte), Err(SSRIError::SSRIMethodsArgsInvalid)));
+            let script_like = ScriptLike { code_hash: [1; 32], hash_type: byte, args: vec![] };
+            assert!(matches!(Script::try_from(&script_like), Err(SSRIError::SSRIMethodsArgsInvalid)));
+            assert!(matches!(script_like.calc_script_hash(), Err(SSRIError::SSRIMethodsArgsInvalid)));
+        }
+    }
+
+    #[test]
+    fn calculates_the_script_hash() {
+        // A secp256k1_blake160_sighash_all lock and its lock hash
+        let script_like = ScriptLike::new(
+            hex("9bd7e06f3ecf4be0f2fcd2188b23f1b9fcc88e5d4b65a8637b17723bbda3cce8")
+                .try_into()
+                .unwrap(),
+            HashType::Type,
+            hex("36c329ed630d6ce750712a477543672adab57f4c"),
+        );
+        assert_eq!(
+            script_like.calc_script_hash().ok().map(|hash| hash.to_vec()),
+            Some(hex("1f2615a8dde4e28ca736ff763c2078aff990043f4cbf09eb4b3a58a140a0862d"))
+        );
+    }
+
+    fn hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).unwrap())
+            .collect()
+    }
 }
@@ -0,0 +1,213 @@
+//! Amount totals of the UDT cells of the current script group
+//!
+//! A UDT cell stores its amount as a little-endian `u128` in the first
+//! [`UDT_LEN`] bytes of its data. With these helpers, the verifications of a
+//! UDT don't have to total the amounts of its cells themselves:
+//!
+//! ```ignore
+//! use ckb_ssri_std::utils::amount::verify_amount_conservation;
+//!
+//! fn verify_transfer() -> Result<(), UDTError> {
+//!     verify_amount_conservation()
+//! }
+//! ```
+
+use ckb_std::ckb_constants::Source;
+use ckb_std::error::SysError;
+
+use crate::public_module_traits::udt::{UDTError, UDT_LEN};
+use crate::utils::syscalls::{DefaultSyscalls, SSRISyscalls};
+
+/// Decode the amount stored as a little-endian `u128` in the first
+/// [`UDT_LEN`] bytes of the data of a UDT cell
+///
+/// # Errors
+///
+/// * Returns `UDTError::InsufficientBalance` if the data is shorter than
+///   [`UDT_LEN`] bytes
+pub fn decode_amount(data: &[u8]) -> Result<u128, UDTError> {
+    let amount = data.get(..UDT_LEN).ok_or(UDTError::InsufficientBalance)?;
+    Ok(u128::from_le_bytes(amount.try_into().unwrap()))
+}
+
+/// Sum the amounts of the cells of `source`, usually `Source::GroupInput` or
+/// `Source::GroupOutput`
+///
+/// Only the first [`UDT_LEN`] bytes of each cell data are loaded.
+///
+/// # Errors
+///
+/// * Returns `UDTError::InsufficientBalance` if a cell data is shorter than
+///   [`UDT_LEN`] bytes or the sum overflows
+/// * Returns `UDTError::Sys` if a cell data cannot be loaded
+pub fn sum_amounts(source: Source) -> Result<u128, UDTError> {
+    sum_amounts_with(&DefaultSyscalls, source)
+}
+
+/// Sum the amounts of the cells of `source` through the given syscall backend
+///
+/// See [`sum_amounts`].
+pub fn sum_amounts_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    source: Source,
+) -> Result<u128, UDTError> {
+    let mut total = 0u128;
+    let mut index = 0;
+    loop {
+        let mut data = [0u8; UDT_LEN];
+        let len = match syscalls.load_cell_data(&mut data, 0, index, source) {
+            Ok(len) | Err(SysError::LengthNotEnough(len)) => len,
+            Err(SysError::IndexOutOfBound) => return Ok(total),
+            Err(err) => return Err(UDTError::Sys(err)),
+        };
+        let amount = decode_amount(&data[..usize::min(len, UDT_LEN)])?;
+        total = total
+            .checked_add(amount)
+            .ok_or(UDTError::InsufficientBalance)?;
+        index += 1;
+    }
+}
+
+/// Total amount of the input cells of the current script group
+///
+/// See [`sum_amounts`].
+pub fn group_input_amount() -> Result<u128, UDTError> {
+    group_input_amount_with(&DefaultSyscalls)
+}
+
+/// Total amount of the input cells of the current script group, loaded
+/// through the given syscall backend
+///
+/// See [`sum_amounts`].
+pub fn group_input_amount_with<S: SSRISyscalls + ?Sized>(syscalls: &S) -> Result<u128, UDTError> {
+    sum_amounts_with(syscalls, Source::GroupInput)
+}
+
+/// Total amount of the output cells of the current script group
+///
+/// See [`sum_amounts`].
+pub fn group_output_amount() -> Result<u128, UDTError> {
+    group_output_amount_with(&DefaultSyscalls)
+}
+
+/// Total amount of the output cells of the current script group, loaded
+/// through the given syscall backend
+///
+/// See [`sum_amounts`].
+pub fn group_output_amount_with<S: SSRISyscalls + ?Sized>(syscalls: &S) -> Result<u128, UDTError> {
+    sum_amounts_with(syscalls, Source::GroupOutput)
+}
+
+/// Check that the current script group doesn't create amounts: the outputs
+/// may hold less than the inputs, which burns the difference, but not more
+///
+/// # Errors
+///
+/// * Returns `UDTError::InsufficientBalance` if the outputs hold more than the
+///   inputs
+/// * Returns the errors of [`sum_amounts`] if a total cannot be computed
+pub fn verify_amount_conservation() -> Result<(), UDTError> {
+    verify_amount_conservation_with(&DefaultSyscalls)
+}
+
+/// Check that the current script group doesn't create amounts, loading the
+/// cells through the given syscall backend
+///
+/// See [`verify_amount_conservation`].
+pub fn verify_amount_conservation_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+) -> Result<(), UDTError> {
+    if group_output_amount_with(syscalls)? > group_input_amount_with(syscalls)? {
+        return Err(UDTError::InsufficientBalance);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::syscalls::{MockSyscalls, SyscallCall};
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn amount_data(amount: u128, extra: usize) -> Vec<u8> {
+        amount.to_le_bytes().into_iter().chain((0..extra).map(|_| 0xff)).collect()
+    }
+
+    fn group(inputs: &[u128], outputs: &[u128]) -> MockSyscalls {
+        let syscalls = inputs.iter().fold(MockSyscalls::default(), |syscalls, amount| {
+            syscalls.with_cell_data(Source::GroupInput, Ok(amount_data(*amount, 0)))
+        });
+        outputs.iter().fold(syscalls, |syscalls, amount| {
+            syscalls.with_cell_data(Source::GroupOutput, Ok(amount_data(*amount, 0)))
+        })
+    }
+
+    #[test]
+    fn decodes_amounts() {
+        assert_eq!(decode_amount(&amount_data(42, 0)).ok(), Some(42));
+        // Bytes past the amount are left to the UDT
+        assert_eq!(decode_amount(&amount_data(42, 8)).ok(), Some(42));
+        for len in [0, 1, UDT_LEN - 1] {
+            assert!(matches!(
+                decode_amount(&amount_data(42, 0)[..len]),
+                Err(UDTError::InsufficientBalance)
+            ));
+        }
+    }
+
+    #[test]
+    fn sums_the_amounts_of_a_source() {
+        let syscalls = MockSyscalls::default()
+            .with_cell_data(Source::GroupInput, Ok(amount_data(10, 0)))
+            .with_cell_data(Source::GroupInput, Ok(amount_data(20, 32)));
+        assert_eq!(group_input_amount_with(&syscalls).ok(), Some(30));
+        assert_eq!(
+            syscalls.calls(),
+            [
+                SyscallCall::LoadCellData(Source::GroupInput, 0, 0),
+                SyscallCall::LoadCellData(Source::GroupInput, 1, 0),
+                SyscallCall::LoadCellData(Source::GroupInput, 2, 0),
+            ]
+        );
+        assert_eq!(group_output_amount_with(&syscalls).ok(), Some(0));
+    }
+
+    #[test]
+    fn rejects_short_data_and_overflows() {
+        let syscalls = MockSyscalls::default()
+            .with_cell_data(Source::GroupInput, Ok(amount_data(10, 0)))
+            .with_cell_data(Source::GroupInput, Ok(vec![1; UDT_LEN - 1]));
+        assert!(matches!(
+            group_input_amount_with(&syscalls),
+            Err(UDTError::InsufficientBalance)
+        ));
+
+        let syscalls = group(&[u128::MAX, 1], &[]);
+        assert!(matches!(
+            group_input_amount_with(&syscalls),
+            Err(UDTError::InsufficientBalance)
+        ));
+
+        let syscalls = MockSyscalls::default().with_cell_data(Source::GroupOutput, Err(SysError::Encoding));
+        assert!(matches!(
+            group_output_amount_with(&syscalls),
+            Err(UDTError::Sys(SysError::Encoding))
+        ));
+    }
+
+    #[test]
+    fn verifies_amount_conservation() {
+        assert!(verify_amount_conservation_with(&group(&[30], &[10, 20])).is_ok());
+        // Outputs holding less than the inputs burn the difference
+        assert!(verify_amount_conservation_with(&group(&[30], &[10])).is_ok());
+        assert!(matches!(
+            verify_amount_conservation_with(&group(&[30], &[10, 21])),
+            Err(UDTError::InsufficientBalance)
+        ));
+        assert!(matches!(
+            verify_amount_conservation_with(&group(&[], &[1])),
+            Err(UDTError::InsufficientBalance)
+        ));
+    }
+}
@@ -0,0 +1,253 @@
+//! Capacity computations for the transactions built by SSRI methods
+//!
+//! Capacities are in shannons; one CKByte is [`SHANNONS_PER_BYTE`] shannons,
+//! and every byte a cell occupies on-chain has to be covered by one CKByte.
+//!
+//! Fee rates are in shannons per 1000 bytes, like the fee rates of the CKB
+//! RPC. With these helpers, an SSRI method can check that the transaction it
+//! returns is balanced, or report how much capacity the caller has to add:
+//!
+//! ```ignore
+//! use ckb_ssri_std::utils::capacity::missing_capacity;
+//!
+//! let missing = missing_capacity(&tx, 1000)?;
+//! ```
+
+use ckb_std::ckb_types::{
+    packed::{CellOutput, Script, Transaction},
+    prelude::*,
+};
+use ckb_std::error::SysError;
+
+use crate::utils::high_level::find_cell_by_out_point_with;
+use crate::utils::syscalls::{DefaultSyscalls, SSRISyscalls};
+
+/// Shannons in one CKByte, the capacity needed per occupied byte
+pub const SHANNONS_PER_BYTE: u64 = 100_000_000;
+
+/// Errors of the capacity totals
+#[derive(Debug, PartialEq, Eq)]
+pub enum CapacityError {
+    /// A total or a fee exceeds `u64::MAX` shannons
+    Overflow,
+    /// An input cell could not be loaded
+    Sys(SysError),
+}
+
+impl From<SysError> for CapacityError {
+    fn from(err: SysError) -> Self {
+        CapacityError::Sys(err)
+    }
+}
+
+/// Minimum capacity, in shannons, of a cell with `output` and `data_len` bytes
+/// of data
+///
+/// The cell occupies its 8-byte capacity field, the code hash, hash type and
+/// args of its lock and type scripts, and its data.
+pub fn occupied_capacity(output: &CellOutput, data_len: usize) -> u64 {
+    let script_len = |script: Script| 32 + 1 + script.args().raw_data().len();
+    let type_len = output.type_().to_opt().map(script_len).unwrap_or(0);
+    (8 + script_len(output.lock()) + type_len + data_len) as u64 * SHANNONS_PER_BYTE
+}
+
+/// Total capacity, in shannons, of the inputs of `tx`, looked up with
+/// `find_cell_by_out_point`
+///
+/// # Errors
+///
+/// * Returns `CapacityError::Sys(SysError::ItemMissing)` if an input cell
+///   cannot be found
+/// * Returns `CapacityError::Overflow` if the total overflows
+pub fn input_capacity(tx: &Transaction) -> Result<u64, CapacityError> {
+    input_capacity_with(&DefaultSyscalls, tx)
+}
+
+/// Total capacity, in shannons, of the inputs of `tx`, looked up through the
+/// given syscall backend
+///
+/// See [`input_capacity`].
+pub fn input_capacity_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    tx: &Transaction,
+) -> Result<u64, CapacityError> {
+    tx.raw().inputs().into_iter().try_fold(0u64, |total, input| {
+        let cell = find_cell_by_out_point_with(syscalls, input.previous_output())?;
+        let capacity: u64 = cell.capacity().unpack();
+        total.checked_add(capacity).ok_or(CapacityError::Overflow)
+    })
+}
+
+/// Total capacity, in shannons, of the outputs of `tx`
+///
+/// # Errors
+///
+/// * Returns `CapacityError::Overflow` if the total overflows
+pub fn output_capacity(tx: &Transaction) -> Result<u64, CapacityError> {
+    tx.raw().outputs().into_iter().try_fold(0u64, |total, output| {
+        let capacity: u64 = output.capacity().unpack();
+        total.checked_add(capacity).ok_or(CapacityError::Overflow)
+    })
+}
+
+/// Size of `tx` in a block, which is the size its fee pays for
+///
+/// This is the size of the serialized transaction plus the 4-byte offset of
+/// the transaction in the block.
+pub fn serialized_size(tx: &Transaction) -> usize {
+    tx.as_slice().len() + 4
+}
+
+/// Fee, in shannons, of a transaction of `size` bytes at `fee_rate` shannons
+/// per 1000 bytes, rounded up
+///
+/// # Errors
+///
+/// * Returns `CapacityError::Overflow` if `size` times `fee_rate` overflows
+pub fn fee(size: usize, fee_rate: u64) -> Result<u64, CapacityError> {
+    let fee = (size as u64)
+        .checked_mul(fee_rate)
+        .ok_or(CapacityError::Overflow)?;
+    Ok(fee.div_ceil(1000))
+}
+
+/// Fee, in shannons, of `tx` at `fee_rate` shannons per 1000 bytes
+///
+/// Witnesses still to be signed have to be filled with placeholders of their
+/// final size for the estimate to be exact.
+///
+/// # Errors
+///
+/// * Returns `CapacityError::Overflow` if the fee overflows
+pub fn estimate_fee(tx: &Transaction, fee_rate: u64) -> Result<u64, CapacityError> {
+    fee(serialized_size(tx), fee_rate)
+}
+
+/// Capacity, in shannons, that the inputs of `tx` lack to cover its outputs
+/// and its fee at `fee_rate`, or 0 if the transaction is balanced
+///
+/// # Errors
+///
+/// * Returns `CapacityError::Sys(SysError::ItemMissing)` if an input cell
+///   cannot be found
+/// * Returns `CapacityError::Overflow` if the inputs, the fee, or the outputs
+///   and the fee overflow
+pub fn missing_capacity(tx: &Transaction, fee_rate: u64) -> Result<u64, CapacityError> {
+    missing_capacity_with(&DefaultSyscalls, tx, fee_rate)
+}
+
+/// Capacity, in shannons, that the inputs of `tx` lack to cover its outputs
+/// and its fee at `fee_rate`, looking the inputs up through the given syscall
+/// backend
+///
+/// See [`missing_capacity`].
+pub fn missing_capacity_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    tx: &Transaction,
+    fee_rate: u64,
+) -> Result<u64, CapacityError> {
+    let required = output_capacity(tx)?
+        .checked_add(estimate_fee(tx, fee_rate)?)
+        .ok_or(CapacityError::Overflow)?;
+    Ok(required.saturating_sub(input_capacity_with(syscalls, tx)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::syscalls::MockSyscalls;
+    use ckb_std::ckb_types::packed::{CellInput, OutPoint, RawTransaction};
+
+    fn cell(capacity: u64) -> CellOutput {
+        CellOutput::new_builder().capacity(capacity.pack()).build()
+    }
+
+    fn out_point(index: u32) -> OutPoint {
+        OutPoint::new_builder().index(index.pack()).build()
+    }
+
+    /// Transaction spending the cells at `out_point(0..inputs)` into outputs
+    /// of the given capacities
+    fn transaction(inputs: u32, outputs: &[u64]) -> Transaction {
+        let raw = RawTransaction::new_builder()
+            .inputs(
+                (0..inputs)
+                    .map(|index| CellInput::new_builder().previous_output(out_point(index)).build())
+                    .pack(),
+            )
+            .outputs(outputs.iter().map(|capacity| cell(*capacity)).pack())
+            .build();
+        Transaction::new_builder().raw(raw).build()
+    }
+
+    #[test]
+    fn computes_occupied_capacity() {
+        let lock = Script::new_builder().args([0; 20].pack()).build();
+        let output = CellOutput::new_builder()
+            .lock(lock.clone())
+            .type_(Some(lock).pack())
+            .build();
+        // 8 for the capacity, 53 for each script and 16 for the data
+        assert_eq!(occupied_capacity(&output, 16), (8 + 53 * 2 + 16) * SHANNONS_PER_BYTE);
+        assert_eq!(occupied_capacity(&CellOutput::default(), 0), 41 * SHANNONS_PER_BYTE);
+    }
+
+    #[test]
+    fn computes_fees_rounded_up() {
+        assert_eq!(fee(1000, 1000), Ok(1000));
+        assert_eq!(fee(1001, 1000), Ok(1001));
+        assert_eq!(fee(1, 1), Ok(1));
+        assert_eq!(fee(0, 1000), Ok(0));
+        let tx = transaction(0, &[]);
+        assert_eq!(estimate_fee(&tx, 1000), Ok(serialized_size(&tx) as u64));
+    }
+
+    #[test]
+    fn totals_inputs_and_outputs() {
+        let syscalls = MockSyscalls::default()
+            .with_cell_by_out_point(out_point(0).as_slice(), Ok(cell(300).as_slice().to_vec()))
+            .with_cell_by_out_point(out_point(1).as_slice(), Ok(cell(700).as_slice().to_vec()));
+        let tx = transaction(2, &[100, 200]);
+        assert_eq!(input_capacity_with(&syscalls, &tx), Ok(1000));
+        assert_eq!(output_capacity(&tx), Ok(300));
+
+        let tx = transaction(3, &[]);
+        assert_eq!(
+            input_capacity_with(&syscalls, &tx),
+            Err(CapacityError::Sys(SysError::ItemMissing))
+        );
+    }
+
+    #[test]
+    fn reports_overflows() {
+        let syscalls = MockSyscalls::default()
+            .with_cell_by_out_point(out_point(0).as_slice(), Ok(cell(u64::MAX).as_slice().to_vec()))
+            .with_cell_by_out_point(out_point(1).as_slice(), Ok(cell(1).as_slice().to_vec()));
+        let tx = transaction(2, &[u64::MAX, 1]);
+        assert_eq!(input_capacity_with(&syscalls, &tx), Err(CapacityError::Overflow));
+        assert_eq!(output_capacity(&tx), Err(CapacityError::Overflow));
+        assert_eq!(missing_capacity_with(&syscalls, &tx, 0), Err(CapacityError::Overflow));
+
+        // The fee alone pushes the outputs over
+        let tx = transaction(1, &[u64::MAX]);
+        assert_eq!(missing_capacity_with(&syscalls, &tx, 1000), Err(CapacityError::Overflow));
+
+        // The fee itself overflows
+        assert_eq!(fee(2, u64::MAX), Err(CapacityError::Overflow));
+        let tx = transaction(1, &[]);
+        assert_eq!(estimate_fee(&tx, u64::MAX), Err(CapacityError::Overflow));
+        assert_eq!(missing_capacity_with(&syscalls, &tx, u64::MAX), Err(CapacityError::Overflow));
+    }
+
+    #[test]
+    fn reports_missing_capacity() {
+        let syscalls = MockSyscalls::default()
+            .with_cell_by_out_point(out_point(0).as_slice(), Ok(cell(10_000).as_slice().to_vec()));
+        let tx = transaction(1, &[9_000]);
+        let fee = estimate_fee(&tx, 1000).unwrap();
+        assert_eq!(missing_capacity_with(&syscalls, &tx, 1000), Ok(0));
+
+        let tx = transaction(1, &[10_000]);
+        assert_eq!(missing_capacity_with(&syscalls, &tx, 1000), Ok(fee));
+    }
+}
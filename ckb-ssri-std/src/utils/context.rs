@@ -0,0 +1,424 @@
+//! Detection of the execution context a script runs in
+//!
+//! A script either validates a transaction on-chain, or serves an SSRI method
+//! in the SSRI-VM. In the SSRI-VM, the caller decides how much context to
+//! supply, which determines the [`ExecutionLevel`]:
+//!
+//! - Code: only the code of the script is available
+//! - Script: the script itself is available through `load_script`
+//! - Transaction: a transaction is available through `load_transaction`
+//! - Chain: the chain is available through `load_tip_header` and the header
+//!   lookups, which are only probed with the `experimental-syscalls` feature
+//!
+//! Methods needing more than the code can declare it with [`require_level`].
+//! The levels are probed from the lowest up, and only as far as needed: a VM
+//! lacking a syscall aborts the script calling it, so the probe of a level is
+//! only attempted once the levels below it are known to be available. The
+//! levels found are cached for the rest of the run.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ckb_ssri_std::utils::context::{require_level, ExecutionLevel};
+//!
+//! fn transfer(to_lock_vec: Vec<Script>, to_amount_vec: Vec<u128>) -> Result<Transaction, Error> {
+//!     require_level(ExecutionLevel::Transaction)?;
+//!     ...
+//! }
+//! ```
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use ckb_std::error::SysError;
+
+use crate::utils::syscalls::{DefaultSyscalls, SSRISyscalls};
+use crate::SSRIError;
+
+/// Context available to an SSRI method, from the least to the most
+///
+/// The `u8` values are the ones reported by `SSRI.get_method_levels`.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ExecutionLevel {
+    Code = 0,
+    Script = 1,
+    Transaction = 2,
+    Chain = 3,
+}
+
+/// Decode a level reported by `SSRI.get_method_levels`, failing with
+/// `SSRIError::SSRIMethodsArgsInvalid` for bytes that are no level, such as
+/// the level reported for an unknown method
+impl TryFrom<u8> for ExecutionLevel {
+    type Error = SSRIError;
+
+    fn try_from(level: u8) -> Result<Self, Self::Error> {
+        match level {
+            0 => Ok(ExecutionLevel::Code),
+            1 => Ok(ExecutionLevel::Script),
+            2 => Ok(ExecutionLevel::Transaction),
+            3 => Ok(ExecutionLevel::Chain),
+            _ => Err(SSRIError::SSRIMethodsArgsInvalid),
+        }
+    }
+}
+
+/// The context a script runs in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExecutionContext {
+    /// Regular CKB-VM validating a transaction
+    OnChainValidation,
+    /// SSRI-VM serving an SSRI method at the given level
+    SSRI(ExecutionLevel),
+}
+
+/// Whether a probing load syscall found its item
+///
+/// The probes load into an empty buffer, so an available item is reported as
+/// `SysError::LengthNotEnough`.
+fn available(res: Result<usize, SysError>) -> bool {
+    matches!(res, Ok(_) | Err(SysError::LengthNotEnough(_)))
+}
+
+/// Whether the chain can be loaded
+///
+/// The header syscalls are experimental, so without the
+/// `experimental-syscalls` feature the Chain level is never detected.
+#[cfg(feature = "experimental-syscalls")]
+fn chain_available<S: SSRISyscalls + ?Sized>(syscalls: &S) -> bool {
+    available(syscalls.load_tip_header(&mut []))
+}
+
+#[cfg(not(feature = "experimental-syscalls"))]
+fn chain_available<S: SSRISyscalls + ?Sized>(_syscalls: &S) -> bool {
+    false
+}
+
+impl ExecutionLevel {
+    /// The level right above this one
+    fn next(self) -> Option<Self> {
+        match self {
+            ExecutionLevel::Code => Some(ExecutionLevel::Script),
+            ExecutionLevel::Script => Some(ExecutionLevel::Transaction),
+            ExecutionLevel::Transaction => Some(ExecutionLevel::Chain),
+            ExecutionLevel::Chain => None,
+        }
+    }
+
+    /// Whether the context of this level can be loaded, assuming the levels
+    /// below it can
+    fn probe<S: SSRISyscalls + ?Sized>(self, syscalls: &S) -> bool {
+        match self {
+            ExecutionLevel::Code => true,
+            ExecutionLevel::Script => available(syscalls.load_script(&mut [], 0)),
+            ExecutionLevel::Transaction => available(syscalls.load_transaction(&mut [], 0)),
+            ExecutionLevel::Chain => chain_available(syscalls),
+        }
+    }
+}
+
+/// Probe the levels above `level`, known to be available, up to `target`
+///
+/// Returns the highest level found, and whether it is the exact level of the
+/// context, i.e. the level above it is missing or doesn't exist.
+fn probe_up_to<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    mut level: ExecutionLevel,
+    target: ExecutionLevel,
+) -> (ExecutionLevel, bool) {
+    while let Some(next) = level.next() {
+        if level >= target {
+            return (level, false);
+        }
+        if !next.probe(syscalls) {
+            return (level, true);
+        }
+        level = next;
+    }
+    (level, true)
+}
+
+/// Whether the script validates a transaction on-chain rather than serving an
+/// SSRI method
+fn on_chain<S: SSRISyscalls + ?Sized>(syscalls: &S) -> bool {
+    crate::utils::syscalls::argv().is_empty() || syscalls.vm_version() != u64::MAX
+}
+
+/// The context found by the probes of the current run
+///
+/// The state is one of `UNPROBED`, `ON_CHAIN`, or an SSRI level, flagged with
+/// `EXACT` once the level above it is known to be missing.
+struct ProbeCache(AtomicU8);
+
+const UNPROBED: u8 = 0xFF;
+const ON_CHAIN: u8 = 0xFE;
+const EXACT: u8 = 0x80;
+
+impl ProbeCache {
+    const fn new() -> Self {
+        ProbeCache(AtomicU8::new(UNPROBED))
+    }
+
+    /// The context of the running script, probed at least up to `target`
+    fn context_up_to<S: SSRISyscalls + ?Sized>(
+        &self,
+        syscalls: &S,
+        target: ExecutionLevel,
+    ) -> ExecutionContext {
+        let (level, exact) = match self.0.load(Ordering::Relaxed) {
+            ON_CHAIN => return ExecutionContext::OnChainValidation,
+            UNPROBED if on_chain(syscalls) => {
+                self.0.store(ON_CHAIN, Ordering::Relaxed);
+                return ExecutionContext::OnChainValidation;
+            }
+            UNPROBED => (ExecutionLevel::Code, false),
+            state => match ExecutionLevel::try_from(state & !EXACT) {
+                Ok(level) => (level, state & EXACT != 0),
+                Err(_) => (ExecutionLevel::Code, false),
+            },
+        };
+        if exact || level >= target {
+            return ExecutionContext::SSRI(level);
+        }
+        let (level, exact) = probe_up_to(syscalls, level, target);
+        let state = if exact { level as u8 | EXACT } else { level as u8 };
+        self.0.store(state, Ordering::Relaxed);
+        ExecutionContext::SSRI(level)
+    }
+}
+
+// The simulator serves each thread its own chain state, and so its own context
+#[cfg(not(feature = "native-simulator"))]
+static PROBES: ProbeCache = ProbeCache::new();
+
+#[cfg(not(feature = "native-simulator"))]
+fn with_probes<R>(f: impl FnOnce(&ProbeCache) -> R) -> R {
+    f(&PROBES)
+}
+
+#[cfg(feature = "native-simulator")]
+std::thread_local! {
+    static PROBES: ProbeCache = const { ProbeCache::new() };
+}
+
+#[cfg(feature = "native-simulator")]
+fn with_probes<R>(f: impl FnOnce(&ProbeCache) -> R) -> R {
+    PROBES.with(f)
+}
+
+/// Forget the context probed so far, for a new chain state of the simulator
+#[cfg(feature = "native-simulator")]
+pub(crate) fn reset_probes() {
+    with_probes(|probes| probes.0.store(UNPROBED, Ordering::Relaxed))
+}
+
+impl ExecutionContext {
+    /// Detect the context of the running script
+    ///
+    /// A script started without arguments, or in a VM other than the SSRI-VM,
+    /// validates a transaction on-chain. Otherwise the level is the highest one
+    /// whose context can be loaded; the probes load into empty buffers and
+    /// don't copy any data.
+    ///
+    /// The result is cached, so that the probes run at most once per run.
+    pub fn detect() -> Self {
+        with_probes(|probes| probes.context_up_to(&DefaultSyscalls, ExecutionLevel::Chain))
+    }
+
+    /// Detect the context of the running script through the given syscall
+    /// backend
+    ///
+    /// See [`ExecutionContext::detect`]; the result is not cached.
+    pub fn detect_with<S: SSRISyscalls + ?Sized>(syscalls: &S) -> Self {
+        ProbeCache::new().context_up_to(syscalls, ExecutionLevel::Chain)
+    }
+
+    /// The level of context available
+    ///
+    /// On-chain validation has the script and the transaction being validated,
+    /// which is the Transaction level.
+    pub fn level(&self) -> ExecutionLevel {
+        match self {
+            ExecutionContext::OnChainValidation => ExecutionLevel::Transaction,
+            ExecutionContext::SSRI(level) => *level,
+        }
+    }
+
+    /// Check that this context provides at least `level`
+    ///
+    /// # Errors
+    ///
+    /// * Returns `SSRIError::SSRIMethodRequireHigherLevel` if it doesn't
+    pub fn require(&self, level: ExecutionLevel) -> Result<(), SSRIError> {
+        if self.level() >= level {
+            Ok(())
+        } else {
+            Err(SSRIError::SSRIMethodRequireHigherLevel)
+        }
+    }
+}
+
+/// Check that the running script has at least `level` of context
+///
+/// Only the levels up to `level` are probed, and the result is cached for the
+/// rest of the run.
+///
+/// # Errors
+///
+/// * Returns `SSRIError::SSRIMethodRequireHigherLevel` if it doesn't
+pub fn require_level(level: ExecutionLevel) -> Result<(), SSRIError> {
+    with_probes(|probes| probes.context_up_to(&DefaultSyscalls, level)).require(level)
+}
+
+/// Check that the script running on the given syscall backend has at least
+/// `level` of context
+///
+/// See [`require_level`]; the result is not cached.
+///
+/// # Errors
+///
+/// * Returns `SSRIError::SSRIMethodRequireHigherLevel` if it doesn't
+pub fn require_level_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    level: ExecutionLevel,
+) -> Result<(), SSRIError> {
+    ProbeCache::new().context_up_to(syscalls, level).require(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::dispatch::UNKNOWN_METHOD_LEVEL;
+    use crate::utils::syscalls::{MockSyscalls, SyscallCall};
+    use alloc::vec;
+
+    // A cache past the argv check, as in the SSRI-VM
+    fn ssri_probes() -> ProbeCache {
+        ProbeCache(AtomicU8::new(ExecutionLevel::Code as u8))
+    }
+
+    fn transaction_context() -> MockSyscalls {
+        MockSyscalls::default()
+            .with_script(Ok(vec![0; 53]))
+            .with_transaction(Ok(vec![0; 68]))
+    }
+
+    #[test]
+    fn decodes_levels() {
+        for level in [
+            ExecutionLevel::Code,
+            ExecutionLevel::Script,
+            ExecutionLevel::Transaction,
+            ExecutionLevel::Chain,
+        ] {
+            assert_eq!(ExecutionLevel::try_from(level as u8).ok(), Some(level));
+        }
+        for byte in [4, UNKNOWN_METHOD_LEVEL] {
+            assert!(matches!(
+                ExecutionLevel::try_from(byte),
+                Err(SSRIError::SSRIMethodsArgsInvalid)
+            ));
+        }
+    }
+
+    #[test]
+    fn probes_from_the_lowest_level() {
+        let syscalls = transaction_context();
+        assert_eq!(
+            probe_up_to(&syscalls, ExecutionLevel::Code, ExecutionLevel::Chain),
+            (ExecutionLevel::Transaction, true)
+        );
+        assert_eq!(
+            syscalls.calls()[..2],
+            [SyscallCall::LoadScript(0), SyscallCall::LoadTransaction(0)]
+        );
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    #[test]
+    fn probes_the_chain_last() {
+        let syscalls = transaction_context().with_tip_header(Ok(vec![0; 208]));
+        assert_eq!(
+            probe_up_to(&syscalls, ExecutionLevel::Code, ExecutionLevel::Chain),
+            (ExecutionLevel::Chain, true)
+        );
+        assert_eq!(
+            syscalls.calls(),
+            [SyscallCall::LoadScript(0), SyscallCall::LoadTransaction(0), SyscallCall::LoadTipHeader]
+        );
+    }
+
+    #[test]
+    fn stops_at_the_first_missing_level() {
+        let syscalls = MockSyscalls::default().with_transaction(Ok(vec![0; 68]));
+        assert_eq!(
+            probe_up_to(&syscalls, ExecutionLevel::Code, ExecutionLevel::Chain),
+            (ExecutionLevel::Code, true)
+        );
+        assert_eq!(syscalls.calls(), [SyscallCall::LoadScript(0)]);
+    }
+
+    #[test]
+    fn probes_only_up_to_the_required_level() {
+        let syscalls = transaction_context();
+        let probes = ssri_probes();
+        assert_eq!(
+            probes.context_up_to(&syscalls, ExecutionLevel::Script),
+            ExecutionContext::SSRI(ExecutionLevel::Script)
+        );
+        assert_eq!(syscalls.calls(), [SyscallCall::LoadScript(0)]);
+    }
+
+    #[test]
+    fn caches_the_levels_probed() {
+        let syscalls = transaction_context();
+        let probes = ssri_probes();
+        probes.context_up_to(&syscalls, ExecutionLevel::Script);
+        syscalls.clear_calls();
+
+        assert_eq!(
+            probes.context_up_to(&syscalls, ExecutionLevel::Script),
+            ExecutionContext::SSRI(ExecutionLevel::Script)
+        );
+        assert_eq!(syscalls.calls(), []);
+        assert_eq!(
+            probes.context_up_to(&syscalls, ExecutionLevel::Transaction),
+            ExecutionContext::SSRI(ExecutionLevel::Transaction)
+        );
+        assert_eq!(syscalls.calls(), [SyscallCall::LoadTransaction(0)]);
+
+        // The exact level is known once a probe failed
+        let syscalls = MockSyscalls::default();
+        let probes = ssri_probes();
+        probes.context_up_to(&syscalls, ExecutionLevel::Chain);
+        syscalls.clear_calls();
+        assert_eq!(
+            probes.context_up_to(&syscalls, ExecutionLevel::Chain),
+            ExecutionContext::SSRI(ExecutionLevel::Code)
+        );
+        assert_eq!(syscalls.calls(), []);
+    }
+
+    #[test]
+    fn requires_the_level() {
+        let probes = ssri_probes();
+        let context = probes.context_up_to(&MockSyscalls::default(), ExecutionLevel::Script);
+        assert!(context.require(ExecutionLevel::Code).is_ok());
+        assert!(matches!(
+            context.require(ExecutionLevel::Script),
+            Err(SSRIError::SSRIMethodRequireHigherLevel)
+        ));
+    }
+
+    #[test]
+    fn detects_on_chain_validation_outside_the_ssri_vm() {
+        let syscalls = transaction_context().with_vm_version(2);
+        assert_eq!(ExecutionContext::detect_with(&syscalls), ExecutionContext::OnChainValidation);
+        assert!(require_level_with(&syscalls, ExecutionLevel::Transaction).is_ok());
+        assert!(require_level_with(&syscalls, ExecutionLevel::Chain).is_err());
+        assert!(!syscalls
+            .calls()
+            .iter()
+            .any(|call| matches!(call, SyscallCall::LoadScript(_) | SyscallCall::LoadTransaction(_))));
+    }
+}
@@ -0,0 +1,456 @@
+//! Runtime support for the dispatchers generated by `ssri_methods!`,
+//! `#[ssri_module]` and `ssri_entry!`
+//!
+//! Every SSRI-compliant script serves the reserved methods of the `SSRI`
+//! namespace:
+//!
+//! - `SSRI.version() -> SSRIVersion`
+//! - `SSRI.get_methods(offset: u64, limit: u64) -> Vec<u64>`
+//! - `SSRI.has_methods(methods: Vec<u64>) -> Vec<bool>`
+//! - `SSRI.get_method_levels(methods: Vec<u64>) -> Vec<u8>`
+//! - `SSRI.get_method_info(method: u64) -> Option<MethodInfo>`, only with the
+//!   `method-info` feature
+//!
+//! `SSRI.version` returns the protocol and contract versions described in
+//! [`crate::utils::version`].
+//! A `limit` of 0 in `SSRI.get_methods` returns all methods after `offset`.
+//! `SSRI.get_method_levels` returns the `ExecutionLevel` each method requires,
+//! or [`UNKNOWN_METHOD_LEVEL`] for methods the script doesn't expose.
+//! `SSRI.get_method_info` returns the name and signature of a method, or empty
+//! bytes for methods the script doesn't expose; the names and type strings are
+//! only compiled into scripts built with the `method-info` feature.
+
+use alloc::{borrow::Cow, vec::Vec};
+use ckb_ssri_std_proc_macro::ssri_method_path;
+use ckb_std::env::Arg;
+
+use crate::codec::{decode_arg, decode_hex_arg, SSRIEncode};
+use crate::utils::context::ExecutionLevel;
+use crate::utils::version::{ContractVersion, SSRIVersion};
+use crate::SSRIError;
+
+/// Method path of `SSRI.version`
+pub const SSRI_VERSION: u64 = ssri_method_path!("SSRI.version");
+/// Method path of `SSRI.get_methods`
+pub const SSRI_GET_METHODS: u64 = ssri_method_path!("SSRI.get_methods");
+/// Method path of `SSRI.has_methods`
+pub const SSRI_HAS_METHODS: u64 = ssri_method_path!("SSRI.has_methods");
+/// Method path of `SSRI.get_method_levels`
+pub const SSRI_GET_METHOD_LEVELS: u64 = ssri_method_path!("SSRI.get_method_levels");
+/// Method path of `SSRI.get_method_info`
+pub const SSRI_GET_METHOD_INFO: u64 = ssri_method_path!("SSRI.get_method_info");
+/// Method paths of the reserved methods, in the order they are listed by `SSRI.get_methods`
+#[cfg(not(feature = "method-info"))]
+pub const RESERVED_METHOD_PATHS: [u64; 4] = [
+    SSRI_VERSION,
+    SSRI_GET_METHODS,
+    SSRI_HAS_METHODS,
+    SSRI_GET_METHOD_LEVELS,
+];
+/// Method paths of the reserved methods, in the order they are listed by `SSRI.get_methods`
+#[cfg(feature = "method-info")]
+pub const RESERVED_METHOD_PATHS: [u64; 5] = [
+    SSRI_VERSION,
+    SSRI_GET_METHODS,
+    SSRI_HAS_METHODS,
+    SSRI_GET_METHOD_LEVELS,
+    SSRI_GET_METHOD_INFO,
+];
+/// Names of the reserved methods, in the order of [`RESERVED_METHOD_PATHS`]
+#[cfg(not(feature = "method-info"))]
+pub const RESERVED_METHOD_NAMES: [&str; 4] = [
+    "SSRI.version",
+    "SSRI.get_methods",
+    "SSRI.has_methods",
+    "SSRI.get_method_levels",
+];
+/// Names of the reserved methods, in the order of [`RESERVED_METHOD_PATHS`]
+#[cfg(feature = "method-info")]
+pub const RESERVED_METHOD_NAMES: [&str; 5] = [
+    "SSRI.version",
+    "SSRI.get_methods",
+    "SSRI.has_methods",
+    "SSRI.get_method_levels",
+    "SSRI.get_method_info",
+];
+/// Execution levels of the reserved methods, which all run at the Code level
+pub const RESERVED_METHOD_LEVELS: [u8; RESERVED_METHOD_PATHS.len()] =
+    [ExecutionLevel::Code as u8; RESERVED_METHOD_PATHS.len()];
+/// Level reported by `SSRI.get_method_levels` for methods the script doesn't expose
+pub const UNKNOWN_METHOD_LEVEL: u8 = u8::MAX;
+/// Names and signatures of the reserved methods
+#[cfg(feature = "method-info")]
+pub const RESERVED_METHOD_INFOS: [MethodInfo; 5] = [
+    MethodInfo {
+        name: "SSRI.version",
+        args: &[],
+        output: "SSRIVersion",
+    },
+    MethodInfo {
+        name: "SSRI.get_methods",
+        args: &["u64", "u64"],
+        output: "Vec<u64>",
+    },
+    MethodInfo {
+        name: "SSRI.has_methods",
+        args: &["Vec<u64>"],
+        output: "Vec<bool>",
+    },
+    MethodInfo {
+        name: "SSRI.get_method_levels",
+        args: &["Vec<u64>"],
+        output: "Vec<u8>",
+    },
+    MethodInfo {
+        name: "SSRI.get_method_info",
+        args: &["u64"],
+        output: "Option<MethodInfo>",
+    },
+];
+
+/// Name and signature of an SSRI method, as returned by `SSRI.get_method_info`
+///
+/// The types are written as in the Rust source of the method, e.g.
+/// `Vec<[u8; 32]>`; a reference parameter `&T` is listed as `T`, and `output`
+/// is the `Ok` type of the method. It is encoded as the molecule table
+/// `table MethodInfo { name: Bytes, args: BytesVec, output: Bytes }`.
+#[cfg(feature = "method-info")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MethodInfo {
+    pub name: &'static str,
+    pub args: &'static [&'static str],
+    pub output: &'static str,
+}
+
+#[cfg(feature = "method-info")]
+impl SSRIEncode for MethodInfo {
+    fn encode(&self) -> Vec<u8> {
+        let bytes = |s: &str| s.as_bytes().to_vec().encode();
+        let args = self.args.iter().map(|arg| bytes(arg)).collect::<Vec<_>>();
+        let fields = [
+            bytes(self.name),
+            serde_molecule::molecule::assemble_table(&args),
+            bytes(self.output),
+        ];
+        serde_molecule::molecule::assemble_table(&fields)
+    }
+}
+
+/// Find a method path occurring more than once across `lists`
+///
+/// Returns the positions, as `(list, index)`, of the first occurrence and of
+/// the colliding one. Used by `ssri_entry!` to reject colliding methods of
+/// different modules at compile time.
+pub const fn find_duplicate_path(
+    lists: &[&[u64]],
+) -> Option<((usize, usize), (usize, usize))> {
+    let mut list = 0;
+    while list < lists.len() {
+        let mut index = 0;
+        while index < lists[list].len() {
+            let path = lists[list][index];
+            let mut other_list = list;
+            let mut other_index = index + 1;
+            while other_list < lists.len() {
+                while other_index < lists[other_list].len() {
+                    if lists[other_list][other_index] == path {
+                        return Some(((list, index), (other_list, other_index)));
+                    }
+                    other_index += 1;
+                }
+                other_list += 1;
+                other_index = 0;
+            }
+            index += 1;
+        }
+        list += 1;
+    }
+    None
+}
+
+/// Concatenate `lists` into a single array
+///
+/// Used by `ssri_entry!` to list the methods of all modules in constants, so
+/// that they are not collected again on every call. `fill` only initializes
+/// the array before the items are copied.
+///
+/// # Panics
+///
+/// Panics if the lists do not hold `N` items in total, which fails the build
+/// when called in a constant.
+pub const fn concat_lists<T: Copy, const N: usize>(lists: &[&[T]], fill: T) -> [T; N] {
+    let mut items = [fill; N];
+    let mut len = 0;
+    let mut list = 0;
+    while list < lists.len() {
+        let mut index = 0;
+        while index < lists[list].len() {
+            assert!(len < N, "the lists hold more items than the array");
+            items[len] = lists[list][index];
+            len += 1;
+            index += 1;
+        }
+        list += 1;
+    }
+    assert!(len == N, "the lists hold fewer items than the array");
+    items
+}
+
+/// Compile-time panic message of `ssri_entry!` naming two colliding methods
+pub struct CollisionMessage {
+    bytes: [u8; 256],
+    len: usize,
+}
+
+impl CollisionMessage {
+    /// Message for methods `first` and `second` sharing a method path,
+    /// truncated to 256 bytes
+    pub const fn new(first: &str, second: &str) -> Self {
+        let parts = [
+            "SSRI modules passed to ssri_entry! expose colliding method paths: `",
+            first,
+            "` and `",
+            second,
+            "`",
+        ];
+        let mut message = CollisionMessage {
+            bytes: [0; 256],
+            len: 0,
+        };
+        let mut part = 0;
+        'parts: while part < parts.len() {
+            let bytes = parts[part].as_bytes();
+            let mut index = 0;
+            while index < bytes.len() {
+                // Copy whole characters only, so that the truncation keeps the
+                // message valid UTF-8
+                let width = match bytes[index] {
+                    0x00..=0x7f => 1,
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    _ => 4,
+                };
+                if message.len + width > message.bytes.len() {
+                    break 'parts;
+                }
+                let mut byte = 0;
+                while byte < width {
+                    message.bytes[message.len] = bytes[index + byte];
+                    message.len += 1;
+                    byte += 1;
+                }
+                index += width;
+            }
+            part += 1;
+        }
+        message
+    }
+
+    pub const fn as_str(&self) -> &str {
+        match core::str::from_utf8(self.bytes.split_at(self.len).0) {
+            Ok(message) => message,
+            Err(_) => "SSRI modules passed to ssri_entry! expose colliding method paths",
+        }
+    }
+}
+
+/// Decode the method path passed in `argv[0]`
+///
+/// # Errors
+///
+/// * Returns `SSRIError::SSRIMethodsNotFound` if `argv[0]` is missing or is not
+///   a hex encoded 8-byte path
+pub fn decode_method_path(argv: &[Arg]) -> Result<u64, SSRIError> {
+    let arg = argv.first().ok_or(SSRIError::SSRIMethodsNotFound)?;
+    let raw = decode_hex_arg(arg).map_err(|_| SSRIError::SSRIMethodsNotFound)?;
+    Ok(u64::from_le_bytes(
+        raw.try_into().map_err(|_| SSRIError::SSRIMethodsNotFound)?,
+    ))
+}
+
+/// List `limit` method paths starting at `offset`, or all remaining ones if `limit` is 0
+pub fn get_methods(methods: &[u64], offset: u64, limit: u64) -> Vec<u64> {
+    let offset = usize::min(offset as usize, methods.len());
+    let end = if limit == 0 {
+        methods.len()
+    } else {
+        usize::min(offset.saturating_add(limit as usize), methods.len())
+    };
+    methods[offset..end].to_vec()
+}
+
+/// Check for each queried path whether it is one of `methods`
+pub fn has_methods(methods: &[u64], queried: &[u64]) -> Vec<bool> {
+    queried.iter().map(|path| methods.contains(path)).collect()
+}
+
+/// Look up the execution level of each queried path
+///
+/// `levels` lists the level of each of `methods`; paths that are not one of
+/// `methods` yield [`UNKNOWN_METHOD_LEVEL`].
+pub fn get_method_levels(methods: &[u64], levels: &[u8], queried: &[u64]) -> Vec<u8> {
+    queried
+        .iter()
+        .map(|path| {
+            methods
+                .iter()
+                .position(|method| method == path)
+                .and_then(|index| levels.get(index).copied())
+                .unwrap_or(UNKNOWN_METHOD_LEVEL)
+        })
+        .collect()
+}
+
+fn call_version(argv: &[Arg], version: ContractVersion) -> Result<Cow<'static, [u8]>, SSRIError> {
+    let [_] = argv else {
+        return Err(SSRIError::SSRIMethodsArgsInvalid);
+    };
+    Ok(SSRIVersion::new(version).into_output())
+}
+
+fn call_get_methods(argv: &[Arg], methods: &[u64]) -> Result<Cow<'static, [u8]>, SSRIError> {
+    let [_, offset, limit] = argv else {
+        return Err(SSRIError::SSRIMethodsArgsInvalid);
+    };
+    let offset = decode_arg::<u64>(offset)?;
+    let limit = decode_arg::<u64>(limit)?;
+    Ok(get_methods(methods, offset, limit).into_output())
+}
+
+fn call_has_methods(argv: &[Arg], methods: &[u64]) -> Result<Cow<'static, [u8]>, SSRIError> {
+    let [_, queried] = argv else {
+        return Err(SSRIError::SSRIMethodsArgsInvalid);
+    };
+    let queried = decode_arg::<Vec<u64>>(queried)?;
+    Ok(has_methods(methods, &queried).into_output())
+}
+
+fn call_get_method_levels(
+    argv: &[Arg],
+    methods: &[u64],
+    levels: &[u8],
+) -> Result<Cow<'static, [u8]>, SSRIError> {
+    let [_, queried] = argv else {
+        return Err(SSRIError::SSRIMethodsArgsInvalid);
+    };
+    let queried = decode_arg::<Vec<u64>>(queried)?;
+    Ok(get_method_levels(methods, levels, &queried).into_output())
+}
+
+/// Look up the name and signature of a method path
+///
+/// `infos` lists the info of each of `methods`; paths that are not one of
+/// `methods` yield `None`.
+#[cfg(feature = "method-info")]
+pub fn get_method_info(methods: &[u64], infos: &[MethodInfo], queried: u64) -> Option<MethodInfo> {
+    methods
+        .iter()
+        .position(|method| *method == queried)
+        .and_then(|index| infos.get(index).copied())
+}
+
+/// Serve a call to `SSRI.get_method_info`
+///
+/// `methods` lists every method path the script exposes, starting with
+/// [`RESERVED_METHOD_PATHS`], and `infos` the info of each of them, starting
+/// with [`RESERVED_METHOD_INFOS`]. Returns `None` if `path` is not
+/// `SSRI.get_method_info`.
+///
+/// # Errors
+///
+/// * Returns `SSRIError::SSRIMethodsArgsInvalid` if the argument count does not
+///   match the method or the argument is malformed
+#[cfg(feature = "method-info")]
+pub fn dispatch_method_info(
+    path: u64,
+    argv: &[Arg],
+    methods: &[u64],
+    infos: &[MethodInfo],
+) -> Option<Result<Cow<'static, [u8]>, SSRIError>> {
+    if path != SSRI_GET_METHOD_INFO {
+        return None;
+    }
+    let [_, queried] = argv else {
+        return Some(Err(SSRIError::SSRIMethodsArgsInvalid));
+    };
+    Some(decode_arg::<u64>(queried).map(|queried| get_method_info(methods, infos, queried).into_output()))
+}
+
+/// Serve a call to one of the reserved `SSRI.*` methods
+///
+/// `methods` lists every method path the script exposes, starting with
+/// [`RESERVED_METHOD_PATHS`], and `levels` the execution level each of them
+/// requires, starting with [`RESERVED_METHOD_LEVELS`]. `version` is the contract
+/// version reported by `SSRI.version`. Returns `None` if `path` is not a
+/// reserved method; `SSRI.get_method_info` is served by `dispatch_method_info`
+/// instead.
+///
+/// # Errors
+///
+/// * Returns `SSRIError::SSRIMethodsArgsInvalid` if the argument count does not
+///   match the method or an argument is malformed
+pub fn dispatch_reserved(
+    path: u64,
+    argv: &[Arg],
+    methods: &[u64],
+    levels: &[u8],
+    version: ContractVersion,
+) -> Option<Result<Cow<'static, [u8]>, SSRIError>> {
+    match path {
+        SSRI_VERSION => Some(call_version(argv, version)),
+        SSRI_GET_METHODS => Some(call_get_methods(argv, methods)),
+        SSRI_HAS_METHODS => Some(call_has_methods(argv, methods)),
+        SSRI_GET_METHOD_LEVELS => Some(call_get_method_levels(argv, methods, levels)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_duplicate_path_reports_both_positions() {
+        assert_eq!(find_duplicate_path(&[&[1, 2], &[3], &[4]]), None);
+        assert_eq!(
+            find_duplicate_path(&[&[1, 2], &[3], &[4, 2]]),
+            Some(((0, 1), (2, 1)))
+        );
+        assert_eq!(find_duplicate_path(&[&[5, 5]]), Some(((0, 0), (0, 1))));
+    }
+
+    #[test]
+    fn concat_lists_keeps_the_order() {
+        const ITEMS: [u8; 5] = concat_lists(&[&[1, 2], &[], &[3, 4, 5]], 0);
+        assert_eq!(ITEMS, [1, 2, 3, 4, 5]);
+        assert!(concat_lists::<u8, 0>(&[&[]], 0).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "fewer items")]
+    fn concat_lists_rejects_short_lists() {
+        concat_lists::<u8, 3>(&[&[1, 2]], 0);
+    }
+
+    #[test]
+    fn reserved_names_match_paths() {
+        for (name, path) in RESERVED_METHOD_NAMES.iter().zip(RESERVED_METHOD_PATHS) {
+            let hash = ckb_hash::blake2b_256(name.as_bytes());
+            assert_eq!(u64::from_le_bytes(hash[..8].try_into().unwrap()), path);
+        }
+    }
+
+    #[test]
+    fn collision_message_names_methods() {
+        let message = CollisionMessage::new("UDT.tip", "Other.tip");
+        assert!(message.as_str().ends_with(": `UDT.tip` and `Other.tip`"));
+    }
+
+    #[test]
+    fn collision_message_truncates_at_char_boundary() {
+        let long = "é".repeat(200);
+        let message = CollisionMessage::new(&long, "UDT.tip");
+        assert!(message.as_str().len() <= 256);
+        assert!(message.as_str().ends_with('é'));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::utils::syscalls;
+use crate::utils::syscalls::{DefaultSyscalls, SSRISyscalls};
 use alloc::vec;
 use alloc::vec::Vec;
 use ckb_std::{
@@ -9,6 +9,10 @@ use ckb_std::{
     error::SysError,
     high_level::BUF_SIZE,
 };
+#[cfg(feature = "experimental-syscalls")]
+use ckb_std::ckb_types::packed::{Byte32, Header, HeaderReader};
+#[cfg(feature = "experimental-syscalls")]
+use core::cell::RefCell;
 
 /// Common method to fully load data from syscall
 fn load_data<F: Fn(&mut [u8], usize) -> Result<usize, SysError>>(
@@ -22,13 +26,40 @@ fn load_data<F: Fn(&mut [u8], usize) -> Result<usize, SysError>>(
             let loaded_len = buf.len();
             data[..loaded_len].copy_from_slice(&buf);
             let len = syscall(&mut data[loaded_len..], loaded_len)?;
-            debug_assert_eq!(len + loaded_len, actual_size);
+            if len + loaded_len != actual_size {
+                return Err(SysError::LengthNotEnough(len + loaded_len));
+            }
             Ok(data)
         }
         Err(err) => Err(err),
     }
 }
 
+/// Common method to load and verify an OutPoint from syscall
+fn load_out_point<F: FnOnce(&mut [u8]) -> Result<usize, SysError>>(
+    syscall: F,
+) -> Result<OutPoint, SysError> {
+    let mut data = [0u8; OutPoint::TOTAL_SIZE];
+    syscall(&mut data)?;
+    match OutPointReader::verify(&data, false) {
+        Ok(()) => Ok(OutPoint::new_unchecked(data.to_vec().into())),
+        Err(_err) => Err(SysError::Encoding),
+    }
+}
+
+/// Common method to load and verify a Header from syscall
+#[cfg(feature = "experimental-syscalls")]
+fn load_header<F: FnOnce(&mut [u8]) -> Result<usize, SysError>>(
+    syscall: F,
+) -> Result<Header, SysError> {
+    let mut data = [0u8; Header::TOTAL_SIZE];
+    syscall(&mut data)?;
+    match HeaderReader::verify(&data, false) {
+        Ok(()) => Ok(Header::new_unchecked(data.to_vec().into())),
+        Err(_err) => Err(SysError::Encoding),
+    }
+}
+
 /// Find an OutPoint by searching for a cell with a specific type script
 ///
 /// Searches the transaction for a cell that matches the given type script
@@ -45,7 +76,7 @@ fn load_data<F: Fn(&mut [u8], usize) -> Result<usize, SysError>>(
 ///
 /// # Example
 ///
-/// ```
+/// ```ignore
 /// let out_point = find_out_point_by_type(type_script).unwrap();
 /// ```
 ///
@@ -59,12 +90,393 @@ fn load_data<F: Fn(&mut [u8], usize) -> Result<usize, SysError>>(
 /// This function can panic if the underlying data is too large,
 /// potentially causing an out-of-memory error.
 pub fn find_out_point_by_type(type_script: Script) -> Result<OutPoint, SysError> {
-    let mut data = [0u8; OutPoint::TOTAL_SIZE];
-    syscalls::find_out_point_by_type(&mut data, &type_script.as_slice())?;
-    match OutPointReader::verify(&data, false) {
-        Ok(()) => Ok(OutPoint::new_unchecked(data.to_vec().into())),
-        Err(_err) => Err(SysError::Encoding),
+    find_out_point_by_type_with(&DefaultSyscalls, type_script)
+}
+
+/// Find an OutPoint by type script through the given syscall backend
+///
+/// See [`find_out_point_by_type`].
+pub fn find_out_point_by_type_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    type_script: Script,
+) -> Result<OutPoint, SysError> {
+    load_out_point(|buf| syscalls.find_out_point_by_type(buf, type_script.as_slice()))
+}
+
+/// Find the OutPoint of the `index`-th cell with a specific type script
+///
+/// # Errors
+///
+/// * Returns `SysError::IndexOutOfBound` if fewer than `index + 1` cells match
+/// * Returns `SysError::ItemMissing` if no cell with matching type script is found
+/// * Returns `SysError::Encoding` if the OutPoint data is malformed
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_type_at(type_script: &Script, index: usize) -> Result<OutPoint, SysError> {
+    find_out_point_by_type_at_with(&DefaultSyscalls, type_script, index)
+}
+
+/// Find the OutPoint of the `index`-th cell with a specific type script
+/// through the given syscall backend
+///
+/// See [`find_out_point_by_type_at`].
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_type_at_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    type_script: &Script,
+    index: usize,
+) -> Result<OutPoint, SysError> {
+    load_out_point(|buf| syscalls.find_out_point_by_type_at(buf, type_script.as_slice(), index))
+}
+
+/// Iterator over the results of an indexed query
+///
+/// Calls `query_fn` with the indices 0, 1, 2, ... and yields the results until
+/// the query fails with `SysError::IndexOutOfBound` or `SysError::ItemMissing`.
+/// Like `ckb_std::high_level::QueryIter`, it panics on any other error.
+///
+/// # Example
+///
+/// ```ignore
+/// let out_points = QueryIter::new(|index| find_out_point_by_type_at(&type_script, index))
+///     .collect::<Vec<_>>();
+/// ```
+pub struct QueryIter<F> {
+    query_fn: F,
+    index: usize,
+}
+
+impl<F> QueryIter<F> {
+    pub fn new(query_fn: F) -> Self {
+        QueryIter { query_fn, index: 0 }
+    }
+}
+
+impl<T, F: Fn(usize) -> Result<T, SysError>> Iterator for QueryIter<F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match (self.query_fn)(self.index) {
+            Ok(item) => {
+                self.index += 1;
+                Some(item)
+            }
+            Err(SysError::IndexOutOfBound) | Err(SysError::ItemMissing) => None,
+            Err(err) => panic!("QueryIter error {:?}", err),
+        }
+    }
+}
+
+/// A cell found by a query
+///
+/// Only the OutPoint is loaded by the query; the CellOutput and the data of the
+/// cell are loaded when requested.
+pub struct FoundCell<'a, S: SSRISyscalls + ?Sized = DefaultSyscalls> {
+    syscalls: &'a S,
+    out_point: OutPoint,
+}
+
+impl<S: SSRISyscalls + ?Sized> FoundCell<'_, S> {
+    /// The OutPoint of the cell
+    pub fn out_point(&self) -> &OutPoint {
+        &self.out_point
     }
+
+    /// Load the CellOutput of the cell
+    pub fn cell_output(&self) -> Result<CellOutput, SysError> {
+        find_cell_by_out_point_with(self.syscalls, self.out_point.clone())
+    }
+
+    /// Load the data of the cell
+    pub fn data(&self) -> Result<Vec<u8>, SysError> {
+        find_cell_data_by_out_point_with(self.syscalls, self.out_point.clone())
+    }
+
+    /// Load at most `len` bytes of the data of the cell, starting at `offset`
+    pub fn data_range(&self, offset: usize, len: usize) -> Result<Vec<u8>, SysError> {
+        find_cell_data_range_by_out_point_with(self.syscalls, self.out_point.clone(), offset, len)
+    }
+}
+
+/// Iterate over the cells at the OutPoints returned by an indexed query
+///
+/// A VM that ignores the index of the query returns the first matching cell
+/// at every index, so the iteration panics on an OutPoint repeated at
+/// consecutive indices instead of looping forever.
+#[cfg(feature = "experimental-syscalls")]
+fn query_cells<'a, S, F>(
+    syscalls: &'a S,
+    find_out_point: F,
+) -> QueryIter<impl Fn(usize) -> Result<FoundCell<'a, S>, SysError> + 'a>
+where
+    S: SSRISyscalls + ?Sized,
+    F: Fn(usize) -> Result<OutPoint, SysError> + 'a,
+{
+    let last_out_point = RefCell::new(None::<OutPoint>);
+    QueryIter::new(move |index| {
+        let out_point = find_out_point(index)?;
+        let last = last_out_point.replace(Some(out_point.clone()));
+        if last.is_some_and(|last| last.as_slice() == out_point.as_slice()) {
+            panic!(
+                "QueryIter error: the same OutPoint was found at index {} and {}",
+                index - 1,
+                index
+            );
+        }
+        Ok(FoundCell { syscalls, out_point })
+    })
+}
+
+/// Iterate over all cells with a specific type script
+///
+/// Each step issues one indexed `SYS_FIND_OUT_POINT_BY_TYPE` syscall; the
+/// CellOutput and the data of a cell are only loaded through the yielded
+/// [`FoundCell`].
+///
+/// # Example
+///
+/// ```ignore
+/// for cell in query_cells_by_type(pause_list_type.clone()) {
+///     let data = cell.data()?;
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if a syscall fails with an error other than `SysError::IndexOutOfBound`
+/// or `SysError::ItemMissing`, or if the same OutPoint is found at two
+/// consecutive indices, as in an SSRI-VM ignoring the index.
+#[cfg(feature = "experimental-syscalls")]
+pub fn query_cells_by_type(
+    type_script: Script,
+) -> QueryIter<impl Fn(usize) -> Result<FoundCell<'static>, SysError>> {
+    query_cells_by_type_with(&DefaultSyscalls, type_script)
+}
+
+/// Iterate over all cells with a specific type script through the given
+/// syscall backend
+///
+/// See [`query_cells_by_type`].
+#[cfg(feature = "experimental-syscalls")]
+pub fn query_cells_by_type_with<'a, S: SSRISyscalls + ?Sized>(
+    syscalls: &'a S,
+    type_script: Script,
+) -> QueryIter<impl Fn(usize) -> Result<FoundCell<'a, S>, SysError> + 'a> {
+    query_cells(syscalls, move |index| {
+        find_out_point_by_type_at_with(syscalls, &type_script, index)
+    })
+}
+
+/// Find an OutPoint by searching for a cell with a specific lock script
+///
+/// Useful to find the cells of an owner.
+///
+/// # Arguments
+///
+/// * `lock_script` - The Script to search for as a cell's lock script
+///
+/// # Returns
+///
+/// * `Ok(OutPoint)` - The OutPoint of the first matching cell
+/// * `Err(SysError)` - A system error if the operation fails
+///
+/// # Example
+///
+/// ```ignore
+/// let out_point = find_out_point_by_lock(owner_lock).unwrap();
+/// ```
+///
+/// # Errors
+///
+/// * Returns `SysError::ItemMissing` if no cell with matching lock script is found
+/// * Returns `SysError::Encoding` if the OutPoint data is malformed
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock(lock_script: Script) -> Result<OutPoint, SysError> {
+    find_out_point_by_lock_with(&DefaultSyscalls, lock_script)
+}
+
+/// Find an OutPoint by lock script through the given syscall backend
+///
+/// See [`find_out_point_by_lock`].
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    lock_script: Script,
+) -> Result<OutPoint, SysError> {
+    load_out_point(|buf| syscalls.find_out_point_by_lock(buf, lock_script.as_slice()))
+}
+
+/// Find the OutPoint of the `index`-th cell with a specific lock script
+///
+/// # Errors
+///
+/// * Returns `SysError::IndexOutOfBound` if fewer than `index + 1` cells match
+/// * Returns `SysError::ItemMissing` if no cell with matching lock script is found
+/// * Returns `SysError::Encoding` if the OutPoint data is malformed
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock_at(lock_script: &Script, index: usize) -> Result<OutPoint, SysError> {
+    find_out_point_by_lock_at_with(&DefaultSyscalls, lock_script, index)
+}
+
+/// Find the OutPoint of the `index`-th cell with a specific lock script
+/// through the given syscall backend
+///
+/// See [`find_out_point_by_lock_at`].
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock_at_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    lock_script: &Script,
+    index: usize,
+) -> Result<OutPoint, SysError> {
+    load_out_point(|buf| syscalls.find_out_point_by_lock_at(buf, lock_script.as_slice(), index))
+}
+
+/// Iterate over all cells with a specific lock script
+///
+/// See [`query_cells_by_type`].
+///
+/// # Example
+///
+/// ```ignore
+/// let owned_cells = query_cells_by_lock(owner_lock.clone()).count();
+/// ```
+#[cfg(feature = "experimental-syscalls")]
+pub fn query_cells_by_lock(
+    lock_script: Script,
+) -> QueryIter<impl Fn(usize) -> Result<FoundCell<'static>, SysError>> {
+    query_cells_by_lock_with(&DefaultSyscalls, lock_script)
+}
+
+/// Iterate over all cells with a specific lock script through the given
+/// syscall backend
+///
+/// See [`query_cells_by_lock`].
+#[cfg(feature = "experimental-syscalls")]
+pub fn query_cells_by_lock_with<'a, S: SSRISyscalls + ?Sized>(
+    syscalls: &'a S,
+    lock_script: Script,
+) -> QueryIter<impl Fn(usize) -> Result<FoundCell<'a, S>, SysError> + 'a> {
+    query_cells(syscalls, move |index| {
+        find_out_point_by_lock_at_with(syscalls, &lock_script, index)
+    })
+}
+
+/// Find an OutPoint by searching for a cell with a specific pair of lock and
+/// type scripts
+///
+/// Useful to find the UDT cells of an owner.
+///
+/// # Arguments
+///
+/// * `lock_script` - The Script to search for as a cell's lock script
+/// * `type_script` - The Script to search for as a cell's type script
+///
+/// # Returns
+///
+/// * `Ok(OutPoint)` - The OutPoint of the first matching cell
+/// * `Err(SysError)` - A system error if the operation fails
+///
+/// # Example
+///
+/// ```ignore
+/// let out_point = find_out_point_by_lock_and_type(owner_lock, udt_type).unwrap();
+/// ```
+///
+/// # Errors
+///
+/// * Returns `SysError::ItemMissing` if no cell with matching scripts is found
+/// * Returns `SysError::Encoding` if the OutPoint data is malformed
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock_and_type(
+    lock_script: Script,
+    type_script: Script,
+) -> Result<OutPoint, SysError> {
+    find_out_point_by_lock_and_type_with(&DefaultSyscalls, lock_script, type_script)
+}
+
+/// Find an OutPoint by lock and type scripts through the given syscall backend
+///
+/// See [`find_out_point_by_lock_and_type`].
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock_and_type_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    lock_script: Script,
+    type_script: Script,
+) -> Result<OutPoint, SysError> {
+    load_out_point(|buf| {
+        syscalls.find_out_point_by_lock_and_type(buf, lock_script.as_slice(), type_script.as_slice())
+    })
+}
+
+/// Find the OutPoint of the `index`-th cell with a specific pair of lock and
+/// type scripts
+///
+/// # Errors
+///
+/// * Returns `SysError::IndexOutOfBound` if fewer than `index + 1` cells match
+/// * Returns `SysError::ItemMissing` if no cell with matching scripts is found
+/// * Returns `SysError::Encoding` if the OutPoint data is malformed
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock_and_type_at(
+    lock_script: &Script,
+    type_script: &Script,
+    index: usize,
+) -> Result<OutPoint, SysError> {
+    find_out_point_by_lock_and_type_at_with(&DefaultSyscalls, lock_script, type_script, index)
+}
+
+/// Find the OutPoint of the `index`-th cell with a specific pair of lock and
+/// type scripts through the given syscall backend
+///
+/// See [`find_out_point_by_lock_and_type_at`].
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock_and_type_at_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    lock_script: &Script,
+    type_script: &Script,
+    index: usize,
+) -> Result<OutPoint, SysError> {
+    load_out_point(|buf| {
+        syscalls.find_out_point_by_lock_and_type_at(
+            buf,
+            lock_script.as_slice(),
+            type_script.as_slice(),
+            index,
+        )
+    })
+}
+
+/// Iterate over all cells with a specific pair of lock and type scripts
+///
+/// See [`query_cells_by_type`].
+///
+/// # Example
+///
+/// ```ignore
+/// for cell in query_cells_by_lock_and_type(owner_lock.clone(), udt_type.clone()) {
+///     let amount = u128::from_le_bytes(cell.data()?[..16].try_into().unwrap());
+/// }
+/// ```
+#[cfg(feature = "experimental-syscalls")]
+pub fn query_cells_by_lock_and_type(
+    lock_script: Script,
+    type_script: Script,
+) -> QueryIter<impl Fn(usize) -> Result<FoundCell<'static>, SysError>> {
+    query_cells_by_lock_and_type_with(&DefaultSyscalls, lock_script, type_script)
+}
+
+/// Iterate over all cells with a specific pair of lock and type scripts
+/// through the given syscall backend
+///
+/// See [`query_cells_by_lock_and_type`].
+#[cfg(feature = "experimental-syscalls")]
+pub fn query_cells_by_lock_and_type_with<'a, S: SSRISyscalls + ?Sized>(
+    syscalls: &'a S,
+    lock_script: Script,
+    type_script: Script,
+) -> QueryIter<impl Fn(usize) -> Result<FoundCell<'a, S>, SysError> + 'a> {
+    query_cells(syscalls, move |index| {
+        find_out_point_by_lock_and_type_at_with(syscalls, &lock_script, &type_script, index)
+    })
 }
 
 /// Find a cell by its OutPoint
@@ -82,7 +494,7 @@ pub fn find_out_point_by_type(type_script: Script) -> Result<OutPoint, SysError>
 ///
 /// # Example
 ///
-/// ```
+/// ```ignore
 /// let out_point = OutPoint::new(...);
 /// let cell_output = find_cell_by_out_point(out_point).unwrap();
 /// ```
@@ -97,8 +509,18 @@ pub fn find_out_point_by_type(type_script: Script) -> Result<OutPoint, SysError>
 /// This function can panic if the underlying data is too large,
 /// potentially causing an out-of-memory error.
 pub fn find_cell_by_out_point(out_point: OutPoint) -> Result<CellOutput, SysError> {
+    find_cell_by_out_point_with(&DefaultSyscalls, out_point)
+}
+
+/// Find a cell by its OutPoint through the given syscall backend
+///
+/// See [`find_cell_by_out_point`].
+pub fn find_cell_by_out_point_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    out_point: OutPoint,
+) -> Result<CellOutput, SysError> {
     let data =
-        load_data(|buf, _offset| syscalls::find_cell_by_out_point(buf, out_point.as_slice()))?;
+        load_data(|buf, offset| syscalls.find_cell_by_out_point(buf, out_point.as_slice(), offset))?;
 
     match CellOutputReader::verify(&data, false) {
         Ok(()) => Ok(CellOutput::new_unchecked(data.into())),
@@ -121,7 +543,7 @@ pub fn find_cell_by_out_point(out_point: OutPoint) -> Result<CellOutput, SysErro
 ///
 /// # Example
 ///
-/// ```
+/// ```ignore
 /// let out_point = OutPoint::new(...);
 /// let data = find_cell_data_by_out_point(out_point).unwrap();
 /// ```
@@ -136,5 +558,332 @@ pub fn find_cell_by_out_point(out_point: OutPoint) -> Result<CellOutput, SysErro
 /// This function can panic if the underlying data is too large,
 /// potentially causing an out-of-memory error.
 pub fn find_cell_data_by_out_point(out_point: OutPoint) -> Result<Vec<u8>, SysError> {
-    load_data(|buf, _offset| syscalls::find_cell_data_by_out_point(buf, out_point.as_slice()))
+    find_cell_data_by_out_point_with(&DefaultSyscalls, out_point)
+}
+
+/// Find cell data by OutPoint through the given syscall backend
+///
+/// See [`find_cell_data_by_out_point`].
+pub fn find_cell_data_by_out_point_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    out_point: OutPoint,
+) -> Result<Vec<u8>, SysError> {
+    load_data(|buf, offset| {
+        syscalls.find_cell_data_by_out_point(buf, out_point.as_slice(), offset)
+    })
+}
+
+/// Load a range of the data of a cell by OutPoint
+///
+/// Loads at most `len` bytes of the cell data starting at `offset`, so that
+/// the head of a big cell (e.g. the header of an icon) can be read without
+/// loading the whole data.
+///
+/// # Arguments
+///
+/// * `out_point` - The OutPoint identifying the cell whose data to retrieve
+/// * `offset` - The offset into the cell data to start loading from
+/// * `len` - The maximum number of bytes to load
+///
+/// # Returns
+///
+/// * `Ok(Vec<u8>)` - The requested bytes, shorter than `len` if the data ends
+///   before `offset + len`
+/// * `Err(SysError)` - A system error if the operation fails
+///
+/// # Example
+///
+/// ```ignore
+/// let magic = find_cell_data_range_by_out_point(out_point, 0, 8).unwrap();
+/// ```
+///
+/// # Errors
+///
+/// * Returns `SysError::ItemMissing` if the cell cannot be found
+pub fn find_cell_data_range_by_out_point(
+    out_point: OutPoint,
+    offset: usize,
+    len: usize,
+) -> Result<Vec<u8>, SysError> {
+    find_cell_data_range_by_out_point_with(&DefaultSyscalls, out_point, offset, len)
+}
+
+/// Load a range of the data of a cell by OutPoint through the given syscall
+/// backend
+///
+/// See [`find_cell_data_range_by_out_point`].
+pub fn find_cell_data_range_by_out_point_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    out_point: OutPoint,
+    offset: usize,
+    len: usize,
+) -> Result<Vec<u8>, SysError> {
+    let mut data = vec![0; len];
+    let loaded_len =
+        match syscalls.find_cell_data_by_out_point(&mut data, out_point.as_slice(), offset) {
+            Ok(actual_len) => actual_len,
+            Err(SysError::LengthNotEnough(_)) => len,
+            Err(err) => return Err(err),
+        };
+    data.truncate(loaded_len);
+    Ok(data)
+}
+
+/// Load the header of the tip block
+///
+/// Gives chain-level SSRI methods access to the current block number, epoch
+/// and timestamp, e.g. to compute the vested amount of a UDT at query time.
+///
+/// # Returns
+///
+/// * `Ok(Header)` - The header of the tip block
+/// * `Err(SysError)` - A system error if the operation fails
+///
+/// # Example
+///
+/// ```ignore
+/// let tip_number: u64 = load_tip_header()?.raw().number().unpack();
+/// ```
+///
+/// # Errors
+///
+/// * Returns `SysError::ItemMissing` if the VM has no chain context
+/// * Returns `SysError::Encoding` if the header data is malformed
+#[cfg(feature = "experimental-syscalls")]
+pub fn load_tip_header() -> Result<Header, SysError> {
+    load_tip_header_with(&DefaultSyscalls)
+}
+
+/// Load the header of the tip block through the given syscall backend
+///
+/// See [`load_tip_header`].
+#[cfg(feature = "experimental-syscalls")]
+pub fn load_tip_header_with<S: SSRISyscalls + ?Sized>(syscalls: &S) -> Result<Header, SysError> {
+    load_header(|buf| syscalls.load_tip_header(buf))
+}
+
+/// Find a header by its block hash
+///
+/// # Arguments
+///
+/// * `block_hash` - The hash of the block
+///
+/// # Returns
+///
+/// * `Ok(Header)` - The header of the block
+/// * `Err(SysError)` - A system error if the operation fails
+///
+/// # Example
+///
+/// ```ignore
+/// let header = find_header_by_block_hash(block_hash).unwrap();
+/// ```
+///
+/// # Errors
+///
+/// * Returns `SysError::ItemMissing` if the block cannot be found
+/// * Returns `SysError::Encoding` if the header data is malformed
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_header_by_block_hash(block_hash: Byte32) -> Result<Header, SysError> {
+    find_header_by_block_hash_with(&DefaultSyscalls, block_hash)
+}
+
+/// Find a header by its block hash through the given syscall backend
+///
+/// See [`find_header_by_block_hash`].
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_header_by_block_hash_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    block_hash: Byte32,
+) -> Result<Header, SysError> {
+    load_header(|buf| syscalls.find_header_by_block_hash(buf, block_hash.as_slice()))
+}
+
+/// Find the header of the block that created an OutPoint
+///
+/// # Arguments
+///
+/// * `out_point` - The OutPoint of the cell
+///
+/// # Returns
+///
+/// * `Ok(Header)` - The header of the block containing the transaction that
+///   created the cell
+/// * `Err(SysError)` - A system error if the operation fails
+///
+/// # Example
+///
+/// ```ignore
+/// let created_at: u64 = find_header_by_out_point(out_point)?.raw().timestamp().unpack();
+/// ```
+///
+/// # Errors
+///
+/// * Returns `SysError::ItemMissing` if the cell or its block cannot be found
+/// * Returns `SysError::Encoding` if the header data is malformed
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_header_by_out_point(out_point: OutPoint) -> Result<Header, SysError> {
+    find_header_by_out_point_with(&DefaultSyscalls, out_point)
+}
+
+/// Find the header of the block that created an OutPoint through the given
+/// syscall backend
+///
+/// See [`find_header_by_out_point`].
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_header_by_out_point_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    out_point: OutPoint,
+) -> Result<Header, SysError> {
+    load_header(|buf| syscalls.find_header_by_out_point(buf, out_point.as_slice()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::syscalls::{MockSyscalls, SyscallCall};
+
+    fn type_script(arg: u8) -> Script {
+        Script::new_builder().args([arg].pack()).build()
+    }
+
+    fn out_point(index: u32) -> OutPoint {
+        OutPoint::new_builder().index(index.pack()).build()
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    #[test]
+    fn finds_out_point_by_type() {
+        let type_script = type_script(1);
+        let syscalls = MockSyscalls::default()
+            .with_out_point_by_type(type_script.as_slice(), Ok(out_point(0).as_slice().to_vec()))
+            .with_out_point_by_type(type_script.as_slice(), Ok(out_point(1).as_slice().to_vec()));
+        let found = find_out_point_by_type_at_with(&syscalls, &type_script, 1).unwrap();
+        assert_eq!(found.as_slice(), out_point(1).as_slice());
+        assert_eq!(
+            syscalls.calls(),
+            [SyscallCall::FindOutPointByType(type_script.as_slice().to_vec(), 1)]
+        );
+    }
+
+    #[test]
+    fn reports_missing_out_points() {
+        let type_script = type_script(1);
+        let syscalls = MockSyscalls::default()
+            .with_out_point_by_type(type_script.as_slice(), Ok(out_point(0).as_slice().to_vec()));
+        assert_eq!(
+            find_out_point_by_type_with(&syscalls, self::type_script(2)).unwrap_err(),
+            SysError::ItemMissing
+        );
+        #[cfg(feature = "experimental-syscalls")]
+        assert_eq!(
+            find_out_point_by_type_at_with(&syscalls, &type_script, 1).unwrap_err(),
+            SysError::IndexOutOfBound
+        );
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    #[test]
+    fn queries_all_cells_by_type() {
+        let type_script = type_script(1);
+        let syscalls = (0..3).fold(MockSyscalls::default(), |syscalls, index| {
+            syscalls.with_out_point_by_type(type_script.as_slice(), Ok(out_point(index).as_slice().to_vec()))
+        });
+        let out_points = query_cells_by_type_with(&syscalls, type_script)
+            .map(|cell| cell.out_point().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(out_points.len(), 3);
+        for (index, found) in out_points.iter().enumerate() {
+            assert_eq!(found.as_slice(), out_point(index as u32).as_slice());
+        }
+        assert!(query_cells_by_type_with(&syscalls, self::type_script(2)).next().is_none());
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    #[test]
+    #[should_panic(expected = "the same OutPoint was found at index 0 and 1")]
+    fn stops_queries_on_repeated_out_points() {
+        // A VM ignoring the index answers every index with the first cell
+        let type_script = type_script(1);
+        let syscalls = (0..3).fold(MockSyscalls::default(), |syscalls, _| {
+            syscalls.with_out_point_by_type(type_script.as_slice(), Ok(out_point(0).as_slice().to_vec()))
+        });
+        query_cells_by_type_with(&syscalls, type_script).for_each(drop);
+    }
+
+    #[test]
+    fn loads_cell_output_and_data() {
+        let output = CellOutput::new_builder().type_(Some(type_script(1)).pack()).build();
+        let data = (0..BUF_SIZE * 2 + 5).map(|byte| byte as u8).collect::<Vec<_>>();
+        let syscalls = MockSyscalls::default()
+            .with_cell_by_out_point(out_point(0).as_slice(), Ok(output.as_slice().to_vec()))
+            .with_cell_data_by_out_point(out_point(0).as_slice(), Ok(data.clone()))
+            .with_cell_by_out_point(out_point(1).as_slice(), Ok(vec![1, 2, 3]));
+
+        let found = find_cell_by_out_point_with(&syscalls, out_point(0)).unwrap();
+        assert_eq!(found.as_slice(), output.as_slice());
+        assert_eq!(
+            find_cell_by_out_point_with(&syscalls, out_point(1)).unwrap_err(),
+            SysError::Encoding
+        );
+
+        // Data larger than the stack buffer is loaded in a second call
+        syscalls.clear_calls();
+        assert_eq!(find_cell_data_by_out_point_with(&syscalls, out_point(0)).unwrap(), data);
+        assert_eq!(
+            syscalls.calls(),
+            [
+                SyscallCall::FindCellDataByOutPoint(out_point(0).as_slice().to_vec(), 0),
+                SyscallCall::FindCellDataByOutPoint(out_point(0).as_slice().to_vec(), BUF_SIZE),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_items_changing_size_between_loads() {
+        // The item loses a byte after the first load
+        let shrunk = load_data(|buf, offset| match offset {
+            0 => Err(SysError::LengthNotEnough(BUF_SIZE + 10)),
+            _ => Ok(buf.len() - 1),
+        });
+        assert_eq!(shrunk.unwrap_err(), SysError::LengthNotEnough(BUF_SIZE + 9));
+    }
+
+    #[test]
+    fn loads_cell_data_ranges() {
+        let data = (0..10).collect::<Vec<u8>>();
+        let syscalls = MockSyscalls::default()
+            .with_cell_data_by_out_point(out_point(0).as_slice(), Ok(data));
+        assert_eq!(
+            find_cell_data_range_by_out_point_with(&syscalls, out_point(0), 2, 3).unwrap(),
+            [2, 3, 4]
+        );
+        assert_eq!(
+            find_cell_data_range_by_out_point_with(&syscalls, out_point(0), 8, 5).unwrap(),
+            [8, 9]
+        );
+        assert_eq!(
+            find_cell_data_range_by_out_point_with(&syscalls, out_point(1), 0, 1).unwrap_err(),
+            SysError::ItemMissing
+        );
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    #[test]
+    fn loads_headers() {
+        let header = Header::new_builder()
+            .raw(ckb_std::ckb_types::packed::RawHeader::new_builder().number(7u64.pack()).build())
+            .build();
+        let syscalls = MockSyscalls::default()
+            .with_tip_header(Ok(header.as_slice().to_vec()))
+            .with_header_by_out_point(out_point(0).as_slice(), Ok(header.as_slice().to_vec()));
+        let tip = load_tip_header_with(&syscalls).unwrap();
+        assert_eq!(tip.as_slice(), header.as_slice());
+        let found = find_header_by_out_point_with(&syscalls, out_point(0)).unwrap();
+        assert_eq!(found.as_slice(), header.as_slice());
+        assert_eq!(
+            find_header_by_block_hash_with(&syscalls, Byte32::default()).unwrap_err(),
+            SysError::ItemMissing
+        );
+    }
 }
@@ -1,21 +1,26 @@
 use ckb_std::debug;
-use syscalls::vm_version;
+use syscalls::{argv, vm_version};
 
 use crate::SSRIError;
 
 pub mod syscalls;
 pub mod high_level;
+pub mod dispatch;
+pub mod context;
+pub mod version;
+pub mod capacity;
+pub mod amount;
+pub mod tx_builder;
+pub mod pause_list;
 
 pub fn should_fallback() -> Result<bool, SSRIError> {
-  if ckb_std::env::argv().is_empty() {
+  if argv().is_empty() {
       debug!("Should fallback!");
-      return Ok(true);
+      Ok(true)
+  } else if vm_version() != u64::MAX {
+      Err(SSRIError::InvalidVmVersion)
   } else {
-      if vm_version() != u64::MAX {
-          return Err(SSRIError::InvalidVmVersion);
-      } else {
-          debug!("Should not fallback!");
-          return Ok(false);
-      }
+      debug!("Should not fallback!");
+      Ok(false)
   }
 }
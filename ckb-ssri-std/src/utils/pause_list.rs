@@ -0,0 +1,500 @@
+//! Traversal of the pause list of a `UDTPausable` token
+//!
+//! The pause list of a token is a chain of [`UDTPausableData`]: each link lists
+//! paused lock hashes and may name, through `next_type_script`, the type script
+//! of the cell holding the next link in its data. [`PauseList`] follows the
+//! chain with `find_out_point_by_type` and `find_cell_data_by_out_point`, and
+//! merges the lock hashes of all links.
+//!
+//! A pause without lock hashes pauses every lock; it is recorded as the
+//! [`GLOBAL_PAUSE_LOCK_HASH`] entry, which no script hashes to. The entry is
+//! kept in [`PauseList::lock_hashes`] but never enumerated.
+//!
+//! `UDTPausable` implementations answer `is_paused` and `enumerate_paused`
+//! with [`is_paused`] and [`enumerate_paused`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ckb_ssri_std::utils::pause_list::{self, PauseList};
+//!
+//! let pause_list = PauseList::from_head(PAUSE_DATA.clone())?;
+//! let page = pause_list.enumerate(offset, limit);
+//!
+//! impl UDTPausable for MyToken {
+//!     fn is_paused(lock_hashes: &Vec<[u8; 32]>) -> Result<Vec<bool>, Self::Error> {
+//!         Ok(pause_list::is_paused(PAUSE_DATA.clone(), lock_hashes)?)
+//!     }
+//!     // ...
+//! }
+//! ```
+
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+use ckb_std::ckb_types::{
+    packed::{Byte32Vec, Script},
+    prelude::*,
+};
+
+use crate::codec::SSRIDecode;
+use crate::public_module_traits::udt::{UDTPausableData, UDTPausableError};
+use crate::utils::high_level::{find_cell_data_by_out_point_with, find_out_point_by_type_with};
+use crate::utils::syscalls::{DefaultSyscalls, SSRISyscalls};
+
+/// Entry of the pause list pausing every lock, recorded when pausing without
+/// lock hashes
+pub const GLOBAL_PAUSE_LOCK_HASH: [u8; 32] = [0; 32];
+
+/// Lock hashes paused by a chain of [`UDTPausableData`], in chain order
+/// without duplicates
+#[derive(Clone, Debug, Default)]
+pub struct PauseList {
+    lock_hashes: Vec<[u8; 32]>,
+    index: BTreeSet<[u8; 32]>,
+}
+
+/// Type script of the cell holding the link after `link`
+fn next_type_script(link: &UDTPausableData) -> Result<Option<Script>, UDTPausableError> {
+    link.next_type_script
+        .as_ref()
+        .map(Script::try_from)
+        .transpose()
+        .map_err(|_| UDTPausableError::IncompletePauseList)
+}
+
+impl PauseList {
+    /// Load the chain starting at the data of the cell with type script
+    /// `first_type`
+    ///
+    /// # Errors
+    ///
+    /// * Returns `UDTPausableError::IncompletePauseList` if a link cannot be
+    ///   found, its data is not a valid `UDTPausableData`, or the hash type
+    ///   of its `next_type_script` is invalid
+    /// * Returns `UDTPausableError::CyclicPauseList` if a link names a type
+    ///   script already visited
+    pub fn load(first_type: Script) -> Result<Self, UDTPausableError> {
+        Self::load_with(&DefaultSyscalls, first_type)
+    }
+
+    /// Load the chain starting at the data of the cell with type script
+    /// `first_type` through the given syscall backend
+    ///
+    /// See [`PauseList::load`].
+    pub fn load_with<S: SSRISyscalls + ?Sized>(
+        syscalls: &S,
+        first_type: Script,
+    ) -> Result<Self, UDTPausableError> {
+        let mut pause_list = PauseList::default();
+        pause_list.follow(syscalls, Some(first_type))?;
+        Ok(pause_list)
+    }
+
+    /// Merge the chain starting at `head`, usually a link built into the
+    /// script itself
+    ///
+    /// # Errors
+    ///
+    /// * Returns the errors of [`PauseList::load`] for the links after `head`
+    pub fn from_head(head: UDTPausableData) -> Result<Self, UDTPausableError> {
+        Self::from_head_with(&DefaultSyscalls, head)
+    }
+
+    /// Merge the chain starting at `head`, loading the following links through
+    /// the given syscall backend
+    ///
+    /// See [`PauseList::from_head`].
+    pub fn from_head_with<S: SSRISyscalls + ?Sized>(
+        syscalls: &S,
+        head: UDTPausableData,
+    ) -> Result<Self, UDTPausableError> {
+        let mut pause_list = PauseList::default();
+        pause_list.extend(&head.pause_list);
+        let next = next_type_script(&head)?;
+        pause_list.follow(syscalls, next)?;
+        Ok(pause_list)
+    }
+
+    /// Merge the links starting at the cell with type script `next`
+    fn follow<S: SSRISyscalls + ?Sized>(
+        &mut self,
+        syscalls: &S,
+        mut next: Option<Script>,
+    ) -> Result<(), UDTPausableError> {
+        let mut visited = Vec::<Script>::new();
+        while let Some(type_script) = next {
+            if visited.iter().any(|script| script.as_slice() == type_script.as_slice()) {
+                return Err(UDTPausableError::CyclicPauseList);
+            }
+            let data = find_out_point_by_type_with(syscalls, type_script.clone())
+                .and_then(|out_point| find_cell_data_by_out_point_with(syscalls, out_point))
+                .map_err(|_| UDTPausableError::IncompletePauseList)?;
+            let link = UDTPausableData::decode(&data)
+                .map_err(|_| UDTPausableError::IncompletePauseList)?;
+            self.extend(&link.pause_list);
+            next = next_type_script(&link)?;
+            visited.push(type_script);
+        }
+        Ok(())
+    }
+
+    fn extend(&mut self, lock_hashes: &[[u8; 32]]) {
+        for lock_hash in lock_hashes {
+            if self.index.insert(*lock_hash) {
+                self.lock_hashes.push(*lock_hash);
+            }
+        }
+    }
+
+    /// Whether `lock_hash` is paused
+    pub fn contains(&self, lock_hash: &[u8; 32]) -> bool {
+        self.index.contains(lock_hash)
+    }
+
+    /// The paused lock hashes, in chain order, including
+    /// [`GLOBAL_PAUSE_LOCK_HASH`] if every lock is paused
+    pub fn lock_hashes(&self) -> &[[u8; 32]] {
+        &self.lock_hashes
+    }
+
+    /// Whether every lock is paused, which pausing without lock hashes
+    /// records as [`GLOBAL_PAUSE_LOCK_HASH`]
+    pub fn is_globally_paused(&self) -> bool {
+        self.contains(&GLOBAL_PAUSE_LOCK_HASH)
+    }
+
+    /// Whether each of `lock_hashes` is paused, or whether every lock is
+    /// paused if `lock_hashes` is empty
+    pub fn is_paused(&self, lock_hashes: &[[u8; 32]]) -> Vec<bool> {
+        let global = self.is_globally_paused();
+        if lock_hashes.is_empty() {
+            return vec![global];
+        }
+        lock_hashes
+            .iter()
+            .map(|lock_hash| global || self.contains(lock_hash))
+            .collect()
+    }
+
+    /// At most `limit` paused lock hashes, starting at the `offset`-th one in
+    /// chain order
+    ///
+    /// [`GLOBAL_PAUSE_LOCK_HASH`] is not a lock hash, so it is skipped and
+    /// doesn't count towards `offset` and `limit`.
+    pub fn enumerate(&self, offset: u64, limit: u64) -> Byte32Vec {
+        let offset = usize::try_from(offset).unwrap_or(usize::MAX);
+        let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+        Byte32Vec::new_builder()
+            .extend(
+                self.lock_hashes
+                    .iter()
+                    .filter(|lock_hash| **lock_hash != GLOBAL_PAUSE_LOCK_HASH)
+                    .skip(offset)
+                    .take(limit)
+                    .map(|lock_hash| lock_hash.pack()),
+            )
+            .build()
+    }
+
+    /// Number of paused lock hashes, which [`PauseList::enumerate`] lists;
+    /// [`GLOBAL_PAUSE_LOCK_HASH`] is not counted
+    pub fn len(&self) -> usize {
+        self.lock_hashes.len() - usize::from(self.is_globally_paused())
+    }
+
+    /// Whether no lock hash is paused individually; every lock may still be
+    /// paused through [`GLOBAL_PAUSE_LOCK_HASH`]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Whether each of `lock_hashes` is paused by the chain starting at `head`,
+/// answering `UDTPausable::is_paused`
+///
+/// See [`PauseList::is_paused`].
+///
+/// # Errors
+///
+/// * Returns the errors of [`PauseList::from_head`]
+pub fn is_paused(
+    head: UDTPausableData,
+    lock_hashes: &[[u8; 32]],
+) -> Result<Vec<bool>, UDTPausableError> {
+    is_paused_with(&DefaultSyscalls, head, lock_hashes)
+}
+
+/// Whether each of `lock_hashes` is paused by the chain starting at `head`,
+/// loading the following links through the given syscall backend
+///
+/// See [`is_paused`].
+pub fn is_paused_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    head: UDTPausableData,
+    lock_hashes: &[[u8; 32]],
+) -> Result<Vec<bool>, UDTPausableError> {
+    Ok(PauseList::from_head_with(syscalls, head)?.is_paused(lock_hashes))
+}
+
+/// At most `limit` lock hashes paused by the chain starting at `head`,
+/// answering `UDTPausable::enumerate_paused`
+///
+/// See [`PauseList::enumerate`].
+///
+/// # Errors
+///
+/// * Returns the errors of [`PauseList::from_head`]
+pub fn enumerate_paused(
+    head: UDTPausableData,
+    offset: u64,
+    limit: u64,
+) -> Result<Byte32Vec, UDTPausableError> {
+    enumerate_paused_with(&DefaultSyscalls, head, offset, limit)
+}
+
+/// At most `limit` lock hashes paused by the chain starting at `head`,
+/// loading the following links through the given syscall backend
+///
+/// See [`enumerate_paused`].
+pub fn enumerate_paused_with<S: SSRISyscalls + ?Sized>(
+    syscalls: &S,
+    head: UDTPausableData,
+    offset: u64,
+    limit: u64,
+) -> Result<Byte32Vec, UDTPausableError> {
+    Ok(PauseList::from_head_with(syscalls, head)?.enumerate(offset, limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::SSRIEncode;
+    use crate::public_module_traits::udt::{ScriptLike, UDTError, UDTPausable, UDT};
+    use crate::SSRIError;
+    use ckb_std::ckb_types::bytes::Bytes;
+    use ckb_std::ckb_types::packed::Transaction;
+    use crate::utils::syscalls::MockSyscalls;
+    use ckb_std::ckb_types::packed::OutPoint;
+
+    fn type_script(arg: u8) -> Script {
+        Script::new_builder().args([arg].pack()).build()
+    }
+
+    fn link(lock_hashes: &[[u8; 32]], next: Option<u8>) -> UDTPausableData {
+        UDTPausableData {
+            pause_list: lock_hashes.to_vec(),
+            next_type_script: next.map(|arg| ScriptLike::from(type_script(arg))),
+        }
+    }
+
+    /// Chain state holding each link in the cell with type script `arg`
+    fn chain(links: &[(u8, UDTPausableData)]) -> MockSyscalls {
+        links.iter().fold(MockSyscalls::default(), |syscalls, (arg, link)| {
+            let out_point = OutPoint::new_builder().index((*arg as u32).pack()).build();
+            syscalls
+                .with_out_point_by_type(type_script(*arg).as_slice(), Ok(out_point.as_slice().to_vec()))
+                .with_cell_data_by_out_point(out_point.as_slice(), Ok(link.encode()))
+        })
+    }
+
+    #[test]
+    fn merges_the_links_in_chain_order() {
+        let syscalls = chain(&[
+            (1, link(&[[1; 32], [2; 32]], Some(2))),
+            (2, link(&[[2; 32], [3; 32]], None)),
+        ]);
+        let pause_list = PauseList::load_with(&syscalls, type_script(1)).unwrap();
+        assert_eq!(pause_list.lock_hashes(), [[1; 32], [2; 32], [3; 32]]);
+        assert_eq!(pause_list.is_paused(&[[3; 32], [4; 32]]), [true, false]);
+        assert_eq!(pause_list.is_paused(&[]), [false]);
+
+        let head = link(&[[4; 32]], Some(1));
+        let pause_list = PauseList::from_head_with(&syscalls, head).unwrap();
+        assert_eq!(pause_list.lock_hashes(), [[4; 32], [1; 32], [2; 32], [3; 32]]);
+    }
+
+    #[test]
+    fn rejects_cyclic_chains() {
+        let syscalls = chain(&[
+            (1, link(&[[1; 32]], Some(2))),
+            (2, link(&[[2; 32]], Some(1))),
+        ]);
+        assert!(matches!(
+            PauseList::load_with(&syscalls, type_script(1)),
+            Err(UDTPausableError::CyclicPauseList)
+        ));
+
+        let syscalls = chain(&[(1, link(&[[1; 32]], Some(1)))]);
+        assert!(matches!(
+            PauseList::from_head_with(&syscalls, link(&[], Some(1))),
+            Err(UDTPausableError::CyclicPauseList)
+        ));
+    }
+
+    #[test]
+    fn enumerates_pages() {
+        let lock_hashes = (1..=5).map(|byte| [byte; 32]).collect::<Vec<_>>();
+        let pause_list =
+            PauseList::from_head_with(&MockSyscalls::default(), link(&lock_hashes, None)).unwrap();
+        let page = |offset, limit| {
+            pause_list
+                .enumerate(offset, limit)
+                .into_iter()
+                .map(|lock_hash| lock_hash.unpack())
+                .collect::<Vec<[u8; 32]>>()
+        };
+        assert_eq!(page(0, 2), lock_hashes[..2]);
+        assert_eq!(page(2, 2), lock_hashes[2..4]);
+        assert_eq!(page(4, 2), lock_hashes[4..]);
+        assert!(page(1, 0).is_empty());
+        assert!(page(5, 2).is_empty());
+        assert!(page(u64::MAX, u64::MAX).is_empty());
+        assert_eq!(page(0, u64::MAX), lock_hashes);
+    }
+
+    #[test]
+    fn records_global_pauses() {
+        let head = link(&[[1; 32], GLOBAL_PAUSE_LOCK_HASH, [2; 32]], None);
+        let pause_list = PauseList::from_head_with(&MockSyscalls::default(), head).unwrap();
+        assert!(pause_list.is_globally_paused());
+        assert_eq!(pause_list.is_paused(&[]), [true]);
+        assert_eq!(pause_list.is_paused(&[[3; 32]]), [true]);
+        let enumerated = pause_list.enumerate(0, u64::MAX);
+        assert_eq!(enumerated.len(), 2);
+        assert_eq!(pause_list.len(), 2);
+        assert!(enumerated.into_iter().all(|lock_hash| lock_hash.raw_data() != GLOBAL_PAUSE_LOCK_HASH[..]));
+
+        let head = link(&[GLOBAL_PAUSE_LOCK_HASH], None);
+        let pause_list = PauseList::from_head_with(&MockSyscalls::default(), head).unwrap();
+        assert!(pause_list.is_globally_paused());
+        assert!(pause_list.is_empty());
+        assert!(pause_list.enumerate(0, u64::MAX).is_empty());
+    }
+
+    #[derive(Debug)]
+    enum TokenError {
+        Udt(UDTError),
+        Pausable(UDTPausableError),
+    }
+
+    impl From<UDTError> for TokenError {
+        fn from(err: UDTError) -> Self {
+            TokenError::Udt(err)
+        }
+    }
+
+    impl From<UDTPausableError> for TokenError {
+        fn from(err: UDTPausableError) -> Self {
+            TokenError::Pausable(err)
+        }
+    }
+
+    fn not_implemented<T>() -> Result<T, TokenError> {
+        Err(UDTError::SSRI(SSRIError::SSRIMethodsNotImplemented).into())
+    }
+
+    /// Token whose pause data is a single global pause link
+    struct GloballyPausedToken;
+
+    impl UDT for GloballyPausedToken {
+        type Error = TokenError;
+
+        fn transfer(_: Option<Transaction>, _: Vec<Script>, _: Vec<u128>) -> Result<Transaction, TokenError> {
+            not_implemented()
+        }
+        fn verify_transfer() -> Result<(), TokenError> {
+            not_implemented()
+        }
+        fn name() -> Result<Bytes, TokenError> {
+            not_implemented()
+        }
+        fn symbol() -> Result<Bytes, TokenError> {
+            not_implemented()
+        }
+        fn decimals() -> Result<u8, TokenError> {
+            not_implemented()
+        }
+        fn icon() -> Result<Bytes, TokenError> {
+            not_implemented()
+        }
+        fn mint(_: Option<Transaction>, _: Vec<Script>, _: Vec<u128>) -> Result<Transaction, TokenError> {
+            not_implemented()
+        }
+        fn verify_mint() -> Result<(), TokenError> {
+            not_implemented()
+        }
+    }
+
+    impl UDTPausable for GloballyPausedToken {
+        fn pause(_: Option<Transaction>, _: &Vec<[u8; 32]>) -> Result<Transaction, TokenError> {
+            not_implemented()
+        }
+        fn unpause(_: Option<Transaction>, _: &Vec<[u8; 32]>) -> Result<Transaction, TokenError> {
+            not_implemented()
+        }
+        fn is_paused(lock_hashes: &Vec<[u8; 32]>) -> Result<Vec<bool>, TokenError> {
+            Ok(is_paused(link(&[[1; 32], GLOBAL_PAUSE_LOCK_HASH], None), lock_hashes)?)
+        }
+        fn enumerate_paused(offset: u64, limit: u64) -> Result<Byte32Vec, TokenError> {
+            Ok(enumerate_paused(link(&[[1; 32], GLOBAL_PAUSE_LOCK_HASH], None), offset, limit)?)
+        }
+    }
+
+    #[test]
+    fn answers_the_pause_queries() {
+        let syscalls = chain(&[(1, link(&[[1; 32]], Some(2))), (2, link(&[[2; 32]], None))]);
+        let head = link(&[[3; 32]], Some(1));
+        assert_eq!(
+            is_paused_with(&syscalls, head.clone(), &[[2; 32], [4; 32]]).unwrap(),
+            [true, false]
+        );
+        let enumerated = enumerate_paused_with(&syscalls, head, 1, 1).unwrap();
+        assert_eq!(enumerated.len(), 1);
+        assert_eq!(enumerated.get(0).unwrap().raw_data(), [1; 32][..]);
+
+        let head = link(&[], Some(3));
+        assert!(matches!(
+            is_paused_with(&syscalls, head.clone(), &[]),
+            Err(UDTPausableError::IncompletePauseList)
+        ));
+        assert!(matches!(
+            enumerate_paused_with(&syscalls, head, 0, 1),
+            Err(UDTPausableError::IncompletePauseList)
+        ));
+    }
+
+    #[test]
+    fn serves_udt_pausable_with_the_helpers() {
+        assert_eq!(GloballyPausedToken::is_paused(&vec![]).unwrap(), [true]);
+        assert_eq!(GloballyPausedToken::is_paused(&vec![[2; 32]]).unwrap(), [true]);
+        let enumerated = GloballyPausedToken::enumerate_paused(0, u64::MAX).unwrap();
+        assert_eq!(enumerated.len(), 1);
+        assert!(matches!(
+            GloballyPausedToken::pause(None, &vec![]),
+            Err(TokenError::Udt(UDTError::SSRI(SSRIError::SSRIMethodsNotImplemented)))
+        ));
+        assert!(matches!(
+            TokenError::from(UDTPausableError::IncompletePauseList),
+            TokenError::Pausable(UDTPausableError::IncompletePauseList)
+        ));
+    }
+
+    #[test]
+    fn rejects_incomplete_chains() {
+        let syscalls = chain(&[(1, link(&[[1; 32]], Some(2)))]);
+        assert!(matches!(
+            PauseList::load_with(&syscalls, type_script(1)),
+            Err(UDTPausableError::IncompletePauseList)
+        ));
+
+        let out_point = OutPoint::new_builder().build();
+        let syscalls = MockSyscalls::default()
+            .with_out_point_by_type(type_script(1).as_slice(), Ok(out_point.as_slice().to_vec()))
+            .with_cell_data_by_out_point(out_point.as_slice(), Ok(vec![1, 2, 3]));
+        assert!(matches!(
+            PauseList::load_with(&syscalls, type_script(1)),
+            Err(UDTPausableError::IncompletePauseList)
+        ));
+    }
+}
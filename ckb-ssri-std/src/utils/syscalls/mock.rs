@@ -0,0 +1,422 @@
+//! Mock backend of the SSRI syscalls for off-chain tests
+//!
+//! [`MockSyscalls`] answers each syscall from responses programmed per input
+//! and records every call it receives, so code written against
+//! [`SSRISyscalls`](super::SSRISyscalls) can be tested without a VM. It is
+//! only built with the `mock` feature, and for the tests of this crate.
+//!
+//! # Example
+//!
+//! ```
+//! use ckb_ssri_std::utils::{high_level, syscalls::{MockSyscalls, SyscallCall}};
+//! use ckb_std::ckb_types::{packed::{OutPoint, Script}, prelude::*};
+//! use ckb_std::error::SysError;
+//!
+//! let type_script = Script::new_builder().args([1u8].pack()).build();
+//! let out_point = OutPoint::new_builder().index(1u32.pack()).build();
+//! let syscalls = MockSyscalls::default()
+//!     .with_out_point_by_type(type_script.as_slice(), Ok(out_point.as_slice().to_vec()));
+//!
+//! let found = high_level::find_out_point_by_type_with(&syscalls, type_script.clone())?;
+//! assert_eq!(found.as_slice(), out_point.as_slice());
+//! assert_eq!(
+//!     syscalls.calls(),
+//!     [SyscallCall::FindOutPointByType(type_script.as_slice().to_vec(), 0)]
+//! );
+//! # Ok::<(), SysError>(())
+//! ```
+
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use ckb_std::ckb_constants::Source;
+
+use super::{load_item, SSRISyscalls, SysError};
+
+/// A syscall received by [`MockSyscalls`], with the bytes and the index or
+/// offset it was called with
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SyscallCall {
+    VmVersion,
+    LoadScript(usize),
+    LoadTransaction(usize),
+    /// The source, index and offset of a `load_cell_data` call
+    LoadCellData(Source, usize, usize),
+    FindOutPointByType(Vec<u8>, usize),
+    #[cfg(feature = "experimental-syscalls")]
+    FindOutPointByLock(Vec<u8>, usize),
+    #[cfg(feature = "experimental-syscalls")]
+    FindOutPointByLockAndType(Vec<u8>, Vec<u8>, usize),
+    FindCellByOutPoint(Vec<u8>, usize),
+    FindCellDataByOutPoint(Vec<u8>, usize),
+    #[cfg(feature = "experimental-syscalls")]
+    LoadTipHeader,
+    #[cfg(feature = "experimental-syscalls")]
+    FindHeaderByBlockHash(Vec<u8>),
+    #[cfg(feature = "experimental-syscalls")]
+    FindHeaderByOutPoint(Vec<u8>),
+}
+
+type Responses = Vec<(Vec<u8>, Result<Vec<u8>, SysError>)>;
+
+/// Syscall backend with programmable responses and call recording
+///
+/// Responses programmed for the same input are served at increasing indices,
+/// so several cells can be returned for one script. Inputs without a
+/// programmed response fail with `SysError::ItemMissing`, and indices past the
+/// last response with `SysError::IndexOutOfBound`.
+///
+/// `vm_version` defaults to `u64::MAX`, the version of the SSRI-VM, and
+/// `load_script` and `load_transaction` fail with `SysError::ItemMissing`
+/// until a response is programmed, as in the SSRI-VM at the Code level.
+#[derive(Clone, Debug)]
+pub struct MockSyscalls {
+    vm_version: u64,
+    script: Responses,
+    transaction: Responses,
+    cell_data: Responses,
+    out_points_by_type: Responses,
+    #[cfg(feature = "experimental-syscalls")]
+    out_points_by_lock: Responses,
+    #[cfg(feature = "experimental-syscalls")]
+    out_points_by_lock_and_type: Responses,
+    cells_by_out_point: Responses,
+    cell_data_by_out_point: Responses,
+    #[cfg(feature = "experimental-syscalls")]
+    tip_header: Responses,
+    #[cfg(feature = "experimental-syscalls")]
+    headers_by_block_hash: Responses,
+    #[cfg(feature = "experimental-syscalls")]
+    headers_by_out_point: Responses,
+    calls: RefCell<Vec<SyscallCall>>,
+}
+
+impl Default for MockSyscalls {
+    fn default() -> Self {
+        MockSyscalls {
+            vm_version: u64::MAX,
+            script: Vec::new(),
+            transaction: Vec::new(),
+            cell_data: Vec::new(),
+            out_points_by_type: Vec::new(),
+            #[cfg(feature = "experimental-syscalls")]
+            out_points_by_lock: Vec::new(),
+            #[cfg(feature = "experimental-syscalls")]
+            out_points_by_lock_and_type: Vec::new(),
+            cells_by_out_point: Vec::new(),
+            cell_data_by_out_point: Vec::new(),
+            #[cfg(feature = "experimental-syscalls")]
+            tip_header: Vec::new(),
+            #[cfg(feature = "experimental-syscalls")]
+            headers_by_block_hash: Vec::new(),
+            #[cfg(feature = "experimental-syscalls")]
+            headers_by_out_point: Vec::new(),
+            calls: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl MockSyscalls {
+    /// Set the version returned by `vm_version`
+    pub fn with_vm_version(mut self, vm_version: u64) -> Self {
+        self.vm_version = vm_version;
+        self
+    }
+
+    /// Answer `load_script` with `response`
+    pub fn with_script(mut self, response: Result<Vec<u8>, SysError>) -> Self {
+        self.script.push((Vec::new(), response));
+        self
+    }
+
+    /// Answer `load_transaction` with `response`
+    pub fn with_transaction(mut self, response: Result<Vec<u8>, SysError>) -> Self {
+        self.transaction.push((Vec::new(), response));
+        self
+    }
+
+    /// Answer `load_cell_data` for `source` with `response`, at the index
+    /// following the responses already programmed for it
+    ///
+    /// Unlike the other syscalls, a source without a programmed response
+    /// fails with `SysError::IndexOutOfBound`, as it has no cells.
+    pub fn with_cell_data(mut self, source: Source, response: Result<Vec<u8>, SysError>) -> Self {
+        self.cell_data.push(((source as u64).to_le_bytes().to_vec(), response));
+        self
+    }
+
+    /// Answer `find_out_point_by_type` for `type_script` with `response`, at
+    /// the index following the responses already programmed for it
+    pub fn with_out_point_by_type(
+        mut self,
+        type_script: &[u8],
+        response: Result<Vec<u8>, SysError>,
+    ) -> Self {
+        self.out_points_by_type.push((type_script.to_vec(), response));
+        self
+    }
+
+    /// Answer `find_out_point_by_lock` for `lock_script` with `response`, at
+    /// the index following the responses already programmed for it
+    #[cfg(feature = "experimental-syscalls")]
+    pub fn with_out_point_by_lock(
+        mut self,
+        lock_script: &[u8],
+        response: Result<Vec<u8>, SysError>,
+    ) -> Self {
+        self.out_points_by_lock.push((lock_script.to_vec(), response));
+        self
+    }
+
+    /// Answer `find_out_point_by_lock_and_type` for the pair of `lock_script`
+    /// and `type_script` with `response`, at the index following the responses
+    /// already programmed for it
+    #[cfg(feature = "experimental-syscalls")]
+    pub fn with_out_point_by_lock_and_type(
+        mut self,
+        lock_script: &[u8],
+        type_script: &[u8],
+        response: Result<Vec<u8>, SysError>,
+    ) -> Self {
+        self.out_points_by_lock_and_type
+            .push(([lock_script, type_script].concat(), response));
+        self
+    }
+
+    /// Answer `find_cell_by_out_point` for `out_point` with `response`
+    pub fn with_cell_by_out_point(
+        mut self,
+        out_point: &[u8],
+        response: Result<Vec<u8>, SysError>,
+    ) -> Self {
+        self.cells_by_out_point.push((out_point.to_vec(), response));
+        self
+    }
+
+    /// Answer `find_cell_data_by_out_point` for `out_point` with `response`
+    pub fn with_cell_data_by_out_point(
+        mut self,
+        out_point: &[u8],
+        response: Result<Vec<u8>, SysError>,
+    ) -> Self {
+        self.cell_data_by_out_point.push((out_point.to_vec(), response));
+        self
+    }
+
+    /// Answer `load_tip_header` with `response`
+    #[cfg(feature = "experimental-syscalls")]
+    pub fn with_tip_header(mut self, response: Result<Vec<u8>, SysError>) -> Self {
+        self.tip_header.push((Vec::new(), response));
+        self
+    }
+
+    /// Answer `find_header_by_block_hash` for `block_hash` with `response`
+    #[cfg(feature = "experimental-syscalls")]
+    pub fn with_header_by_block_hash(
+        mut self,
+        block_hash: &[u8],
+        response: Result<Vec<u8>, SysError>,
+    ) -> Self {
+        self.headers_by_block_hash.push((block_hash.to_vec(), response));
+        self
+    }
+
+    /// Answer `find_header_by_out_point` for `out_point` with `response`
+    #[cfg(feature = "experimental-syscalls")]
+    pub fn with_header_by_out_point(
+        mut self,
+        out_point: &[u8],
+        response: Result<Vec<u8>, SysError>,
+    ) -> Self {
+        self.headers_by_out_point.push((out_point.to_vec(), response));
+        self
+    }
+
+    /// The calls received so far, in order
+    pub fn calls(&self) -> Vec<SyscallCall> {
+        self.calls.borrow().clone()
+    }
+
+    /// Forget the calls received so far
+    pub fn clear_calls(&self) {
+        self.calls.borrow_mut().clear();
+    }
+
+    fn respond(
+        &self,
+        call: SyscallCall,
+        responses: &Responses,
+        input: &[u8],
+        index: usize,
+        offset: usize,
+        buf: &mut [u8],
+    ) -> Result<usize, SysError> {
+        self.calls.borrow_mut().push(call);
+        let mut matches = responses.iter().filter(|(key, _)| key == input);
+        match matches.nth(index) {
+            Some((_, Ok(data))) => load_item(buf, data, offset),
+            Some((_, Err(err))) => Err(*err),
+            None if index == 0 => Err(SysError::ItemMissing),
+            None => Err(SysError::IndexOutOfBound),
+        }
+    }
+}
+
+impl SSRISyscalls for MockSyscalls {
+    fn vm_version(&self) -> u64 {
+        self.calls.borrow_mut().push(SyscallCall::VmVersion);
+        self.vm_version
+    }
+
+    fn load_script(&self, buf: &mut [u8], offset: usize) -> Result<usize, SysError> {
+        self.respond(SyscallCall::LoadScript(offset), &self.script, &[], 0, offset, buf)
+    }
+
+    fn load_transaction(&self, buf: &mut [u8], offset: usize) -> Result<usize, SysError> {
+        self.respond(SyscallCall::LoadTransaction(offset), &self.transaction, &[], 0, offset, buf)
+    }
+
+    fn load_cell_data(
+        &self,
+        buf: &mut [u8],
+        offset: usize,
+        index: usize,
+        source: Source,
+    ) -> Result<usize, SysError> {
+        // A source without cells has no item at index 0 either, as in CKB
+        self.calls.borrow_mut().push(SyscallCall::LoadCellData(source, index, offset));
+        let key = (source as u64).to_le_bytes();
+        let mut matches = self.cell_data.iter().filter(|(input, _)| *input == key);
+        match matches.nth(index) {
+            Some((_, Ok(data))) => load_item(buf, data, offset),
+            Some((_, Err(err))) => Err(*err),
+            None => Err(SysError::IndexOutOfBound),
+        }
+    }
+
+    fn find_out_point_by_type(&self, buf: &mut [u8], type_script: &[u8]) -> Result<usize, SysError> {
+        self.respond(
+            SyscallCall::FindOutPointByType(type_script.to_vec(), 0),
+            &self.out_points_by_type,
+            type_script,
+            0,
+            0,
+            buf,
+        )
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_out_point_by_type_at(
+        &self,
+        buf: &mut [u8],
+        type_script: &[u8],
+        index: usize,
+    ) -> Result<usize, SysError> {
+        self.respond(
+            SyscallCall::FindOutPointByType(type_script.to_vec(), index),
+            &self.out_points_by_type,
+            type_script,
+            index,
+            0,
+            buf,
+        )
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_out_point_by_lock_at(
+        &self,
+        buf: &mut [u8],
+        lock_script: &[u8],
+        index: usize,
+    ) -> Result<usize, SysError> {
+        self.respond(
+            SyscallCall::FindOutPointByLock(lock_script.to_vec(), index),
+            &self.out_points_by_lock,
+            lock_script,
+            index,
+            0,
+            buf,
+        )
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_out_point_by_lock_and_type_at(
+        &self,
+        buf: &mut [u8],
+        lock_script: &[u8],
+        type_script: &[u8],
+        index: usize,
+    ) -> Result<usize, SysError> {
+        // Molecule scripts start with their total size, so the concatenation
+        // identifies the pair unambiguously
+        self.respond(
+            SyscallCall::FindOutPointByLockAndType(lock_script.to_vec(), type_script.to_vec(), index),
+            &self.out_points_by_lock_and_type,
+            &[lock_script, type_script].concat(),
+            index,
+            0,
+            buf,
+        )
+    }
+
+    fn find_cell_by_out_point(
+        &self,
+        buf: &mut [u8],
+        out_point: &[u8],
+        offset: usize,
+    ) -> Result<usize, SysError> {
+        self.respond(
+            SyscallCall::FindCellByOutPoint(out_point.to_vec(), offset),
+            &self.cells_by_out_point,
+            out_point,
+            0,
+            offset,
+            buf,
+        )
+    }
+
+    fn find_cell_data_by_out_point(
+        &self,
+        buf: &mut [u8],
+        out_point: &[u8],
+        offset: usize,
+    ) -> Result<usize, SysError> {
+        self.respond(
+            SyscallCall::FindCellDataByOutPoint(out_point.to_vec(), offset),
+            &self.cell_data_by_out_point,
+            out_point,
+            0,
+            offset,
+            buf,
+        )
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    fn load_tip_header(&self, buf: &mut [u8]) -> Result<usize, SysError> {
+        self.respond(SyscallCall::LoadTipHeader, &self.tip_header, &[], 0, 0, buf)
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_header_by_block_hash(&self, buf: &mut [u8], block_hash: &[u8]) -> Result<usize, SysError> {
+        self.respond(
+            SyscallCall::FindHeaderByBlockHash(block_hash.to_vec()),
+            &self.headers_by_block_hash,
+            block_hash,
+            0,
+            0,
+            buf,
+        )
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_header_by_out_point(&self, buf: &mut [u8], out_point: &[u8]) -> Result<usize, SysError> {
+        self.respond(
+            SyscallCall::FindHeaderByOutPoint(out_point.to_vec()),
+            &self.headers_by_out_point,
+            out_point,
+            0,
+            0,
+            buf,
+        )
+    }
+}
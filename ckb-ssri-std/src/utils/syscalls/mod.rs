@@ -1,6 +1,8 @@
 // re-export to maintain compatible with old versions
 pub use ckb_std::error::SysError;
 
+use ckb_std::ckb_constants::Source;
+
 #[cfg(not(feature = "native-simulator"))]
 mod native;
 #[cfg(not(feature = "native-simulator"))]
@@ -9,4 +11,234 @@ pub use native::*;
 #[cfg(feature = "native-simulator")]
 mod simulator;
 #[cfg(feature = "native-simulator")]
-pub use simulator::*;
\ No newline at end of file
+pub use simulator::*;
+
+#[cfg(any(test, feature = "mock"))]
+mod mock;
+#[cfg(any(test, feature = "mock"))]
+pub use mock::*;
+
+/// Backend of the SSRI-specific syscalls
+///
+/// The helpers in `utils::high_level` are generic over this trait so that the
+/// code built on them can run against [`DefaultSyscalls`] in the SSRI-VM and
+/// against `MockSyscalls`, enabled by the `mock` feature, in off-chain tests.
+///
+/// The `find_*` methods follow the semantics of the CKB load syscalls: the item
+/// is copied into `buf`, its full length is returned, and
+/// `SysError::LengthNotEnough(len)` is returned if `buf` is too small to hold it.
+/// The methods loading cells take an `offset` into the item; the copy then
+/// starts at `offset` and the length returned is the one remaining from it.
+pub trait SSRISyscalls {
+    /// Version of the running VM, `u64::MAX` in the SSRI-VM
+    fn vm_version(&self) -> u64;
+
+    /// Load the running script, as `ckb_std::syscalls::load_script`
+    fn load_script(&self, buf: &mut [u8], offset: usize) -> Result<usize, SysError>;
+
+    /// Load the transaction validated or supplied to the SSRI-VM, as
+    /// `ckb_std::syscalls::load_transaction`
+    fn load_transaction(&self, buf: &mut [u8], offset: usize) -> Result<usize, SysError>;
+
+    /// Load the data of the `index`-th cell of `source`, as
+    /// `ckb_std::syscalls::load_cell_data`
+    ///
+    /// Fails with `SysError::IndexOutOfBound` past the last cell of `source`.
+    fn load_cell_data(
+        &self,
+        buf: &mut [u8],
+        offset: usize,
+        index: usize,
+        source: Source,
+    ) -> Result<usize, SysError>;
+
+    /// Find the OutPoint of the first cell with the given type script
+    fn find_out_point_by_type(&self, buf: &mut [u8], type_script: &[u8]) -> Result<usize, SysError>;
+
+    /// Find the OutPoint of the `index`-th cell with the given type script
+    ///
+    /// Fails with `SysError::IndexOutOfBound` past the last matching cell, or
+    /// with `SysError::ItemMissing` if no cell matches at all.
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_out_point_by_type_at(
+        &self,
+        buf: &mut [u8],
+        type_script: &[u8],
+        index: usize,
+    ) -> Result<usize, SysError>;
+
+    /// Find the OutPoint of the first cell with the given lock script
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_out_point_by_lock(&self, buf: &mut [u8], lock_script: &[u8]) -> Result<usize, SysError> {
+        self.find_out_point_by_lock_at(buf, lock_script, 0)
+    }
+
+    /// Find the OutPoint of the `index`-th cell with the given lock script
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_out_point_by_lock_at(
+        &self,
+        buf: &mut [u8],
+        lock_script: &[u8],
+        index: usize,
+    ) -> Result<usize, SysError>;
+
+    /// Find the OutPoint of the first cell with the given lock and type scripts
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_out_point_by_lock_and_type(
+        &self,
+        buf: &mut [u8],
+        lock_script: &[u8],
+        type_script: &[u8],
+    ) -> Result<usize, SysError> {
+        self.find_out_point_by_lock_and_type_at(buf, lock_script, type_script, 0)
+    }
+
+    /// Find the OutPoint of the `index`-th cell with the given lock and type scripts
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_out_point_by_lock_and_type_at(
+        &self,
+        buf: &mut [u8],
+        lock_script: &[u8],
+        type_script: &[u8],
+        index: usize,
+    ) -> Result<usize, SysError>;
+
+    /// Find the CellOutput of the cell at the given OutPoint
+    fn find_cell_by_out_point(
+        &self,
+        buf: &mut [u8],
+        out_point: &[u8],
+        offset: usize,
+    ) -> Result<usize, SysError>;
+
+    /// Find the data of the cell at the given OutPoint
+    fn find_cell_data_by_out_point(
+        &self,
+        buf: &mut [u8],
+        out_point: &[u8],
+        offset: usize,
+    ) -> Result<usize, SysError>;
+
+    /// Load the header of the tip block
+    #[cfg(feature = "experimental-syscalls")]
+    fn load_tip_header(&self, buf: &mut [u8]) -> Result<usize, SysError>;
+
+    /// Find the header of the block with the given hash
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_header_by_block_hash(&self, buf: &mut [u8], block_hash: &[u8]) -> Result<usize, SysError>;
+
+    /// Find the header of the block that created the given OutPoint
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_header_by_out_point(&self, buf: &mut [u8], out_point: &[u8]) -> Result<usize, SysError>;
+}
+
+/// The syscalls of this module, issued through the `ecall` path of the VM
+///
+/// With the `native-simulator` feature, the calls are served by the simulator
+/// instead.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultSyscalls;
+
+impl SSRISyscalls for DefaultSyscalls {
+    fn vm_version(&self) -> u64 {
+        vm_version()
+    }
+
+    fn load_script(&self, buf: &mut [u8], offset: usize) -> Result<usize, SysError> {
+        load_script(buf, offset)
+    }
+
+    fn load_transaction(&self, buf: &mut [u8], offset: usize) -> Result<usize, SysError> {
+        load_transaction(buf, offset)
+    }
+
+    fn load_cell_data(
+        &self,
+        buf: &mut [u8],
+        offset: usize,
+        index: usize,
+        source: Source,
+    ) -> Result<usize, SysError> {
+        load_cell_data(buf, offset, index, source)
+    }
+
+    fn find_out_point_by_type(&self, buf: &mut [u8], type_script: &[u8]) -> Result<usize, SysError> {
+        find_out_point_by_type(buf, type_script)
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_out_point_by_type_at(
+        &self,
+        buf: &mut [u8],
+        type_script: &[u8],
+        index: usize,
+    ) -> Result<usize, SysError> {
+        find_out_point_by_type_at(buf, type_script, index)
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_out_point_by_lock_at(
+        &self,
+        buf: &mut [u8],
+        lock_script: &[u8],
+        index: usize,
+    ) -> Result<usize, SysError> {
+        find_out_point_by_lock_at(buf, lock_script, index)
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_out_point_by_lock_and_type_at(
+        &self,
+        buf: &mut [u8],
+        lock_script: &[u8],
+        type_script: &[u8],
+        index: usize,
+    ) -> Result<usize, SysError> {
+        find_out_point_by_lock_and_type_at(buf, lock_script, type_script, index)
+    }
+
+    fn find_cell_by_out_point(
+        &self,
+        buf: &mut [u8],
+        out_point: &[u8],
+        offset: usize,
+    ) -> Result<usize, SysError> {
+        find_cell_by_out_point(buf, out_point, offset)
+    }
+
+    fn find_cell_data_by_out_point(
+        &self,
+        buf: &mut [u8],
+        out_point: &[u8],
+        offset: usize,
+    ) -> Result<usize, SysError> {
+        find_cell_data_by_out_point(buf, out_point, offset)
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    fn load_tip_header(&self, buf: &mut [u8]) -> Result<usize, SysError> {
+        load_tip_header(buf)
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_header_by_block_hash(&self, buf: &mut [u8], block_hash: &[u8]) -> Result<usize, SysError> {
+        find_header_by_block_hash(buf, block_hash)
+    }
+
+    #[cfg(feature = "experimental-syscalls")]
+    fn find_header_by_out_point(&self, buf: &mut [u8], out_point: &[u8]) -> Result<usize, SysError> {
+        find_header_by_out_point(buf, out_point)
+    }
+}
+
+/// Copy `data` from `offset` on into `buf` with the semantics of a CKB load syscall
+#[cfg(any(test, feature = "mock", feature = "native-simulator"))]
+fn load_item(buf: &mut [u8], data: &[u8], offset: usize) -> Result<usize, SysError> {
+    let data = &data[usize::min(offset, data.len())..];
+    let len = usize::min(buf.len(), data.len());
+    buf[..len].copy_from_slice(&data[..len]);
+    if data.len() > buf.len() {
+        return Err(SysError::LengthNotEnough(data.len()));
+    }
+    Ok(data.len())
+}
@@ -1,7 +1,11 @@
 #[cfg(target_arch = "riscv64")]
 use core::arch::asm;
 
-use ckb_std::{ckb_constants::SYS_VM_VERSION, error::SysError};
+use ckb_std::{
+    ckb_constants::{Source, SYS_VM_VERSION},
+    env::Arg,
+    error::SysError,
+};
 
 /// System call number for finding an OutPoint by type script
 pub const SYS_FIND_OUT_POINT_BY_TYPE: u64 = 2277;
@@ -9,7 +13,34 @@ pub const SYS_FIND_OUT_POINT_BY_TYPE: u64 = 2277;
 pub const SYS_FIND_CELL_BY_OUT_POINT: u64 = 2287;
 /// System call number for finding cell data by OutPoint
 pub const SYS_FIND_CELL_DATA_BY_OUT_POINT: u64 = 2297;
+/// System call number for finding an OutPoint by lock script
+///
+/// Experimental: no SSRI-VM implements it yet, and the number may change.
+#[cfg(feature = "experimental-syscalls")]
+pub const SYS_FIND_OUT_POINT_BY_LOCK: u64 = 2307;
+/// System call number for finding an OutPoint by lock and type script
+///
+/// Experimental: no SSRI-VM implements it yet, and the number may change.
+#[cfg(feature = "experimental-syscalls")]
+pub const SYS_FIND_OUT_POINT_BY_LOCK_AND_TYPE: u64 = 2317;
+/// System call number for loading the tip header
+#[cfg(feature = "experimental-syscalls")]
+pub const SYS_LOAD_TIP_HEADER: u64 = 2327;
+/// System call number for finding a header by block hash
+#[cfg(feature = "experimental-syscalls")]
+pub const SYS_FIND_HEADER_BY_BLOCK_HASH: u64 = 2337;
+/// System call number for finding the header of the block that created an OutPoint
+#[cfg(feature = "experimental-syscalls")]
+pub const SYS_FIND_HEADER_BY_OUT_POINT: u64 = 2347;
+/// System call number for setting the content returned by an SSRI method
+pub const SYS_SET_CONTENT: u64 = 2103;
 
+/// Raw CKB-VM syscall
+///
+/// # Safety
+///
+/// The arguments are passed to the VM as is; pointer arguments must be valid
+/// for the memory the syscall reads or writes.
 #[cfg(target_arch = "riscv64")]
 #[allow(clippy::too_many_arguments)]
 pub unsafe fn syscall(
@@ -36,6 +67,11 @@ pub unsafe fn syscall(
     a0
 }
 
+/// Raw CKB-VM syscall stub for non-RISC-V targets, always returning `u64::MAX`
+///
+/// # Safety
+///
+/// Has no effect; marked `unsafe` to keep the signature of the RISC-V version.
 #[cfg(not(target_arch = "riscv64"))]
 #[allow(clippy::too_many_arguments)]
 pub unsafe fn syscall(
@@ -55,10 +91,36 @@ pub fn vm_version() -> u64 {
     unsafe { syscall(0, 0, 0, 0, 0, 0, 0, SYS_VM_VERSION) }
 }
 
+/// Arguments the script was started with, as `ckb_std::env::argv`
+pub fn argv() -> &'static [Arg] {
+    ckb_std::env::argv()
+}
+
+/// Load the running script, as `ckb_std::syscalls::load_script`
+pub fn load_script(buf: &mut [u8], offset: usize) -> Result<usize, SysError> {
+    ckb_std::syscalls::load_script(buf, offset)
+}
+
+/// Load the transaction, as `ckb_std::syscalls::load_transaction`
+pub fn load_transaction(buf: &mut [u8], offset: usize) -> Result<usize, SysError> {
+    ckb_std::syscalls::load_transaction(buf, offset)
+}
+
+/// Load the data of a cell, as `ckb_std::syscalls::load_cell_data`
+pub fn load_cell_data(
+    buf: &mut [u8],
+    offset: usize,
+    index: usize,
+    source: Source,
+) -> Result<usize, SysError> {
+    ckb_std::syscalls::load_cell_data(buf, offset, index, source)
+}
+
 
 
 /// Load data
 /// Return data length or syscall error
+#[allow(clippy::too_many_arguments)]
 fn syscall_load(
     buf_ptr: *mut u8,
     len: usize,
@@ -107,6 +169,70 @@ fn build_syscall_result(
 }
 
 
+/// Load the item found at an OutPoint from `offset` on
+///
+/// The syscalls taking an OutPoint read it from `a2`. With the
+/// `experimental-syscalls` feature, `offset` is passed in `a3`, as no SSRI-VM
+/// reads it yet; otherwise `a3` is 0 and the item is loaded from its start into
+/// a buffer large enough to copy the bytes from `offset` on out of it.
+#[cfg(feature = "experimental-syscalls")]
+fn load_out_point_item(
+    buf: &mut [u8],
+    out_point: &[u8],
+    offset: usize,
+    syscall_num: u64,
+) -> Result<usize, SysError> {
+    syscall_load(
+        buf.as_mut_ptr(),
+        buf.len(),
+        out_point.as_ptr() as usize,
+        offset as u64,
+        0,
+        0,
+        0,
+        syscall_num,
+    )
+}
+
+/// Load the item found at an OutPoint from `offset` on
+///
+/// See the `experimental-syscalls` version.
+#[cfg(not(feature = "experimental-syscalls"))]
+fn load_out_point_item(
+    buf: &mut [u8],
+    out_point: &[u8],
+    offset: usize,
+    syscall_num: u64,
+) -> Result<usize, SysError> {
+    let load = |data: &mut [u8]| {
+        syscall_load(
+            data.as_mut_ptr(),
+            data.len(),
+            out_point.as_ptr() as usize,
+            0,
+            0,
+            0,
+            0,
+            syscall_num,
+        )
+    };
+    if offset == 0 {
+        return load(buf);
+    }
+    let mut data = alloc::vec![0; offset + buf.len()];
+    let item_len = match load(&mut data) {
+        Ok(len) | Err(SysError::LengthNotEnough(len)) => len,
+        Err(err) => return Err(err),
+    };
+    let remaining = item_len.saturating_sub(offset);
+    let copied = usize::min(remaining, buf.len());
+    buf[..copied].copy_from_slice(&data[offset..offset + copied]);
+    if remaining > buf.len() {
+        return Err(SysError::LengthNotEnough(remaining));
+    }
+    Ok(remaining)
+}
+
 /// Find an OutPoint by searching for a specific type script
 ///
 /// Searches for a cell with the given type script and returns its OutPoint.
@@ -143,19 +269,200 @@ pub fn find_out_point_by_type(
     )
 }
 
+/// Find the OutPoint of the `index`-th cell with a specific type script
+///
+/// Indexed variant of [`find_out_point_by_type`], passing `index` to
+/// `SYS_FIND_OUT_POINT_BY_TYPE` in `a4` so that all matching cells can be
+/// enumerated. Index 0 is the cell returned by [`find_out_point_by_type`].
+///
+/// Experimental: SSRI-VMs that do not read `a4` return the first matching
+/// cell at every index.
+///
+/// # Arguments
+///
+/// * `buf` - A mutable buffer to receive the OutPoint data
+/// * `type_script` - The serialized type script to search for
+/// * `index` - The position of the cell among the matching cells
+///
+/// # Returns
+///
+/// * `Ok(usize)` - The actual length of the OutPoint data written to the buffer
+/// * `Err(SysError)` - A system error if the operation fails
+///
+/// # Errors
+///
+/// Returns `SysError::LengthNotEnough` if the buffer is too small to hold the data
+/// Returns `SysError::IndexOutOfBound` if fewer than `index + 1` cells match
+/// Returns `SysError::ItemMissing` if no matching cell is found
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_type_at(
+    buf: &mut [u8],
+    type_script: &[u8],
+    index: usize,
+) -> Result<usize, SysError> {
+    syscall_load(
+        buf.as_mut_ptr(),
+        buf.len(),
+        type_script.as_ptr() as usize,
+        type_script.len() as u64,
+        index as u64,
+        0,
+        0,
+        SYS_FIND_OUT_POINT_BY_TYPE,
+    )
+}
+
+/// Find an OutPoint by searching for a specific lock script
+///
+/// Searches for a cell with the given lock script and returns its OutPoint.
+/// The OutPoint data is written to the provided buffer.
+///
+/// # Arguments
+///
+/// * `buf` - A mutable buffer to receive the OutPoint data
+/// * `lock_script` - The serialized lock script to search for
+///
+/// # Returns
+///
+/// * `Ok(usize)` - The actual length of the OutPoint data written to the buffer
+/// * `Err(SysError)` - A system error if the operation fails
+///
+/// # Errors
+///
+/// Returns `SysError::LengthNotEnough` if the buffer is too small to hold the data
+/// Returns `SysError::IndexOutOfBound` if the lock script is invalid
+/// Returns `SysError::ItemMissing` if no matching cell is found
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock(
+    buf: &mut [u8],
+    lock_script: &[u8],
+) -> Result<usize, SysError> {
+    find_out_point_by_lock_at(buf, lock_script, 0)
+}
+
+/// Find the OutPoint of the `index`-th cell with a specific lock script
+///
+/// # Arguments
+///
+/// * `buf` - A mutable buffer to receive the OutPoint data
+/// * `lock_script` - The serialized lock script to search for
+/// * `index` - The position of the cell among the matching cells
+///
+/// # Returns
+///
+/// * `Ok(usize)` - The actual length of the OutPoint data written to the buffer
+/// * `Err(SysError)` - A system error if the operation fails
+///
+/// # Errors
+///
+/// Returns `SysError::LengthNotEnough` if the buffer is too small to hold the data
+/// Returns `SysError::IndexOutOfBound` if fewer than `index + 1` cells match
+/// Returns `SysError::ItemMissing` if no matching cell is found
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock_at(
+    buf: &mut [u8],
+    lock_script: &[u8],
+    index: usize,
+) -> Result<usize, SysError> {
+    syscall_load(
+        buf.as_mut_ptr(),
+        buf.len(),
+        lock_script.as_ptr() as usize,
+        lock_script.len() as u64,
+        index as u64,
+        0,
+        0,
+        SYS_FIND_OUT_POINT_BY_LOCK,
+    )
+}
+
+/// Find an OutPoint by searching for a specific pair of lock and type scripts
+///
+/// Searches for a cell with both the given lock script and the given type
+/// script and returns its OutPoint. The OutPoint data is written to the
+/// provided buffer.
+///
+/// # Arguments
+///
+/// * `buf` - A mutable buffer to receive the OutPoint data
+/// * `lock_script` - The serialized lock script to search for
+/// * `type_script` - The serialized type script to search for
+///
+/// # Returns
+///
+/// * `Ok(usize)` - The actual length of the OutPoint data written to the buffer
+/// * `Err(SysError)` - A system error if the operation fails
+///
+/// # Errors
+///
+/// Returns `SysError::LengthNotEnough` if the buffer is too small to hold the data
+/// Returns `SysError::IndexOutOfBound` if one of the scripts is invalid
+/// Returns `SysError::ItemMissing` if no matching cell is found
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock_and_type(
+    buf: &mut [u8],
+    lock_script: &[u8],
+    type_script: &[u8],
+) -> Result<usize, SysError> {
+    find_out_point_by_lock_and_type_at(buf, lock_script, type_script, 0)
+}
+
+/// Find the OutPoint of the `index`-th cell with a specific pair of lock and
+/// type scripts
+///
+/// # Arguments
+///
+/// * `buf` - A mutable buffer to receive the OutPoint data
+/// * `lock_script` - The serialized lock script to search for
+/// * `type_script` - The serialized type script to search for
+/// * `index` - The position of the cell among the matching cells
+///
+/// # Returns
+///
+/// * `Ok(usize)` - The actual length of the OutPoint data written to the buffer
+/// * `Err(SysError)` - A system error if the operation fails
+///
+/// # Errors
+///
+/// Returns `SysError::LengthNotEnough` if the buffer is too small to hold the data
+/// Returns `SysError::IndexOutOfBound` if fewer than `index + 1` cells match
+/// Returns `SysError::ItemMissing` if no matching cell is found
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock_and_type_at(
+    buf: &mut [u8],
+    lock_script: &[u8],
+    type_script: &[u8],
+    index: usize,
+) -> Result<usize, SysError> {
+    syscall_load(
+        buf.as_mut_ptr(),
+        buf.len(),
+        lock_script.as_ptr() as usize,
+        lock_script.len() as u64,
+        type_script.as_ptr() as u64,
+        type_script.len() as u64,
+        index as u64,
+        SYS_FIND_OUT_POINT_BY_LOCK_AND_TYPE,
+    )
+}
+
 /// Find a cell by its OutPoint
 ///
 /// Retrieves cell information using the specified OutPoint.
 /// The cell data is written to the provided buffer.
 ///
+/// The OutPoint is passed in `a2`; `offset` is only passed in `a3` with the
+/// `experimental-syscalls` feature and is applied by this function otherwise.
+///
 /// # Arguments
 ///
 /// * `buf` - A mutable buffer to receive the cell data
 /// * `out_point` - The serialized OutPoint identifying the cell to find
+/// * `offset` - The offset into the CellOutput to start loading from
 ///
 /// # Returns
 ///
-/// * `Ok(usize)` - The actual length of the cell data written to the buffer
+/// * `Ok(usize)` - The length of the CellOutput data from `offset` on
 /// * `Err(SysError)` - A system error if the operation fails
 ///
 /// # Errors
@@ -166,17 +473,9 @@ pub fn find_out_point_by_type(
 pub fn find_cell_by_out_point(
     buf: &mut [u8],
     out_point: &[u8],
+    offset: usize,
 ) -> Result<usize, SysError> {
-    syscall_load(
-        buf.as_mut_ptr(),
-        buf.len(),
-        out_point.as_ptr() as usize,
-        0,
-        0,
-        0,
-        0,
-        SYS_FIND_CELL_BY_OUT_POINT,
-    )
+    load_out_point_item(buf, out_point, offset, SYS_FIND_CELL_BY_OUT_POINT)
 }
 
 /// Find cell data by OutPoint
@@ -184,14 +483,18 @@ pub fn find_cell_by_out_point(
 /// Retrieves the data contained in a cell identified by the specified OutPoint.
 /// The cell's data is written to the provided buffer.
 ///
+/// The OutPoint is passed in `a2`; `offset` is only passed in `a3` with the
+/// `experimental-syscalls` feature and is applied by this function otherwise.
+///
 /// # Arguments
 ///
 /// * `buf` - A mutable buffer to receive the cell's data
 /// * `out_point` - The serialized OutPoint identifying the cell whose data to retrieve
+/// * `offset` - The offset into the cell data to start loading from
 ///
 /// # Returns
 ///
-/// * `Ok(usize)` - The actual length of the cell data written to the buffer
+/// * `Ok(usize)` - The length of the cell data from `offset` on
 /// * `Err(SysError)` - A system error if the operation fails
 ///
 /// # Errors
@@ -202,6 +505,103 @@ pub fn find_cell_by_out_point(
 pub fn find_cell_data_by_out_point(
     buf: &mut [u8],
     out_point: &[u8],
+    offset: usize,
+) -> Result<usize, SysError> {
+    load_out_point_item(buf, out_point, offset, SYS_FIND_CELL_DATA_BY_OUT_POINT)
+}
+
+/// Load the header of the tip block
+///
+/// Retrieves the header of the tip block of the chain the SSRI-VM runs
+/// against. The header is written to the provided buffer.
+///
+/// # Arguments
+///
+/// * `buf` - A mutable buffer to receive the header data
+///
+/// # Returns
+///
+/// * `Ok(usize)` - The actual length of the header data written to the buffer
+/// * `Err(SysError)` - A system error if the operation fails
+///
+/// # Errors
+///
+/// Returns `SysError::LengthNotEnough` if the buffer is too small to hold the data
+/// Returns `SysError::ItemMissing` if the VM has no chain context
+#[cfg(feature = "experimental-syscalls")]
+pub fn load_tip_header(buf: &mut [u8]) -> Result<usize, SysError> {
+    syscall_load(
+        buf.as_mut_ptr(),
+        buf.len(),
+        0,
+        0,
+        0,
+        0,
+        0,
+        SYS_LOAD_TIP_HEADER,
+    )
+}
+
+/// Find a header by its block hash
+///
+/// Retrieves the header of the block with the specified hash.
+/// The header is written to the provided buffer.
+///
+/// # Arguments
+///
+/// * `buf` - A mutable buffer to receive the header data
+/// * `block_hash` - The serialized hash of the block
+///
+/// # Returns
+///
+/// * `Ok(usize)` - The actual length of the header data written to the buffer
+/// * `Err(SysError)` - A system error if the operation fails
+///
+/// # Errors
+///
+/// Returns `SysError::LengthNotEnough` if the buffer is too small to hold the data
+/// Returns `SysError::ItemMissing` if the block cannot be found
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_header_by_block_hash(
+    buf: &mut [u8],
+    block_hash: &[u8],
+) -> Result<usize, SysError> {
+    syscall_load(
+        buf.as_mut_ptr(),
+        buf.len(),
+        block_hash.as_ptr() as usize,
+        0,
+        0,
+        0,
+        0,
+        SYS_FIND_HEADER_BY_BLOCK_HASH,
+    )
+}
+
+/// Find the header of the block that created an OutPoint
+///
+/// Retrieves the header of the block containing the transaction that created
+/// the cell at the specified OutPoint. The header is written to the provided
+/// buffer.
+///
+/// # Arguments
+///
+/// * `buf` - A mutable buffer to receive the header data
+/// * `out_point` - The serialized OutPoint of the cell
+///
+/// # Returns
+///
+/// * `Ok(usize)` - The actual length of the header data written to the buffer
+/// * `Err(SysError)` - A system error if the operation fails
+///
+/// # Errors
+///
+/// Returns `SysError::LengthNotEnough` if the buffer is too small to hold the data
+/// Returns `SysError::ItemMissing` if the cell or its block cannot be found
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_header_by_out_point(
+    buf: &mut [u8],
+    out_point: &[u8],
 ) -> Result<usize, SysError> {
     syscall_load(
         buf.as_mut_ptr(),
@@ -211,7 +611,45 @@ pub fn find_cell_data_by_out_point(
         0,
         0,
         0,
-        SYS_FIND_CELL_DATA_BY_OUT_POINT,
+        SYS_FIND_HEADER_BY_OUT_POINT,
     )
 }
 
+/// Set the content returned by an SSRI method
+///
+/// Hands the encoded result of an SSRI method over to the SSRI-VM, which
+/// returns it to the caller.
+///
+/// # Arguments
+///
+/// * `content` - The bytes to return
+///
+/// # Returns
+///
+/// * `Ok(())` - The content has been set
+/// * `Err(SysError)` - A system error if the operation fails
+///
+/// # Errors
+///
+/// Returns `SysError::Unknown` if the VM does not support setting content
+pub fn set_content(content: &[u8]) -> Result<(), SysError> {
+    let mut len = content.len() as u64;
+    let ret = unsafe {
+        syscall(
+            content.as_ptr() as u64,
+            &mut len as *mut u64 as u64,
+            0,
+            0,
+            0,
+            0,
+            0,
+            SYS_SET_CONTENT,
+        )
+    };
+    match ret {
+        0 => Ok(()),
+        1 => Err(SysError::IndexOutOfBound),
+        2 => Err(SysError::ItemMissing),
+        _ => Err(SysError::Unknown(ret)),
+    }
+}
@@ -0,0 +1,947 @@
+//! Host-side simulation of the SSRI syscalls
+//!
+//! Enabled with the `native-simulator` feature, this backend serves the SSRI
+//! syscalls from an in-memory [`ChainState`] instead of the SSRI-VM, so the
+//! SSRI logic of a contract can be unit-tested natively. The state is kept per
+//! thread; install it with [`ChainState::load`] before calling into the
+//! contract, and read the content set by the method with [`take_content`].
+//!
+//! The state also holds the `argv` of the call, which selects the method run
+//! by `program_entry`; without it, `program_entry` runs the fallback. The
+//! script and transaction of the state are served by `load_script` and
+//! `load_transaction`, which set the execution level of the call.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ckb_ssri_std::ssri_method_path;
+//! use ckb_ssri_std::utils::syscalls::{take_content, ChainState};
+//!
+//! ChainState::from_json(include_str!("fixtures/pause_list.json"))?
+//!     .with_call(ssri_method_path!("UDT.is_paused"), &[&lock_hashes.encode()])
+//!     .load();
+//! assert_eq!(program_entry(), 0);
+//! let content = take_content().unwrap();
+//! ```
+
+use std::boxed::Box;
+use std::cell::{Cell, RefCell};
+use std::ffi::CString;
+use std::string::String;
+use std::sync::Mutex;
+use std::vec::Vec;
+
+use ckb_std::{
+    ckb_constants::Source,
+    env::Arg,
+    ckb_types::{
+        bytes::Bytes,
+        packed::{Byte32, CellOutput, Header, OutPoint, RawHeader, Script, Transaction},
+        prelude::*,
+    },
+    error::SysError,
+};
+use serde::Deserialize;
+
+use super::load_item;
+
+/// A live cell of the simulated chain
+#[derive(Clone, Debug)]
+pub struct SimulatedCell {
+    pub out_point: OutPoint,
+    pub output: CellOutput,
+    pub data: Bytes,
+    /// Hash of the block that created the cell, if known
+    pub block_hash: Option<Byte32>,
+}
+
+/// In-memory chain state the simulated syscalls are served from
+#[derive(Clone, Debug)]
+pub struct ChainState {
+    pub cells: Vec<SimulatedCell>,
+    /// Known block headers; the one with the highest number is the tip
+    pub headers: Vec<Header>,
+    pub vm_version: u64,
+    /// Arguments of the call returned by [`argv`]: the hex encoded method path
+    /// followed by the hex encoded arguments of the method
+    pub argv: Vec<String>,
+    /// Script returned by `load_script`, missing below the Script level
+    pub script: Option<Script>,
+    /// Transaction returned by `load_transaction`, missing below the
+    /// Transaction level
+    pub transaction: Option<Transaction>,
+}
+
+impl Default for ChainState {
+    /// An empty chain state running in the SSRI-VM
+    fn default() -> Self {
+        ChainState {
+            cells: Vec::new(),
+            headers: Vec::new(),
+            vm_version: u64::MAX,
+            argv: Vec::new(),
+            script: None,
+            transaction: None,
+        }
+    }
+}
+
+impl ChainState {
+    /// Add a live cell to the chain state
+    pub fn with_cell(mut self, out_point: OutPoint, output: CellOutput, data: Bytes) -> Self {
+        self.cells.push(SimulatedCell {
+            out_point,
+            output,
+            data,
+            block_hash: None,
+        });
+        self
+    }
+
+    /// Add a live cell created in the block with the given hash
+    pub fn with_cell_in_block(
+        mut self,
+        out_point: OutPoint,
+        output: CellOutput,
+        data: Bytes,
+        block_hash: Byte32,
+    ) -> Self {
+        self.cells.push(SimulatedCell {
+            out_point,
+            output,
+            data,
+            block_hash: Some(block_hash),
+        });
+        self
+    }
+
+    /// Add a block header to the chain state
+    pub fn with_header(mut self, header: Header) -> Self {
+        self.headers.push(header);
+        self
+    }
+
+    /// Set the version returned by `vm_version`
+    pub fn with_vm_version(mut self, vm_version: u64) -> Self {
+        self.vm_version = vm_version;
+        self
+    }
+
+    /// Set the script returned by `load_script`
+    pub fn with_script(mut self, script: Script) -> Self {
+        self.script = Some(script);
+        self
+    }
+
+    /// Set the transaction returned by `load_transaction`
+    pub fn with_transaction(mut self, transaction: Transaction) -> Self {
+        self.transaction = Some(transaction);
+        self
+    }
+
+    /// Set the arguments returned by [`argv`]
+    pub fn with_argv(mut self, argv: Vec<String>) -> Self {
+        self.argv = argv;
+        self
+    }
+
+    /// Set the arguments calling the SSRI method with the given path, e.g.
+    /// `ssri_method_path!("UDT.name")`, with the encoded `args`
+    pub fn with_call(self, method_path: u64, args: &[&[u8]]) -> Self {
+        let argv = core::iter::once(encode_hex(&method_path.to_le_bytes()))
+            .chain(args.iter().map(|arg| encode_hex(arg)))
+            .collect();
+        self.with_argv(argv)
+    }
+
+    /// Load a chain state from a JSON fixture
+    ///
+    /// Cells use the JSON format of the CKB RPC, with the cell data as hex:
+    ///
+    /// ```json
+    /// {
+    ///   "vm_version": "0xffffffffffffffff",
+    ///   "argv": ["<hex method path>", "<hex argument>"],
+    ///   "script": { "code_hash": "0x...", "hash_type": "type", "args": "0x" },
+    ///   "cells": [{
+    ///     "out_point": { "tx_hash": "0x...", "index": "0x0" },
+    ///     "output": {
+    ///       "capacity": "0x174876e800",
+    ///       "lock": { "code_hash": "0x...", "hash_type": "type", "args": "0x" },
+    ///       "type": null
+    ///     },
+    ///     "data": "0x",
+    ///     "block_hash": "0x..."
+    ///   }],
+    ///   "headers": [{
+    ///     "version": "0x0",
+    ///     "compact_target": "0x1e015555",
+    ///     "timestamp": "0x18c8d0ab7e0",
+    ///     "number": "0x1",
+    ///     "epoch": "0x7080018000001",
+    ///     "parent_hash": "0x...",
+    ///     "transactions_root": "0x...",
+    ///     "proposals_hash": "0x...",
+    ///     "extra_hash": "0x...",
+    ///     "dao": "0x...",
+    ///     "nonce": "0x0"
+    ///   }]
+    /// }
+    /// ```
+    ///
+    /// `vm_version` is optional and defaults to the SSRI-VM version `u64::MAX`.
+    /// `argv`, `script`, `headers` and the `block_hash` of the cells are
+    /// optional as well; the header with the highest number is the tip. The
+    /// transaction can only be set with [`ChainState::with_transaction`].
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let fixture: JsonChainState = serde_json::from_str(json).map_err(|err| err.to_string())?;
+        let mut state = ChainState::default();
+        if let Some(vm_version) = fixture.vm_version {
+            state.vm_version = parse_u64(&vm_version)?;
+        }
+        state.argv = fixture.argv;
+        state.script = fixture.script.map(|script| script.to_script()).transpose()?;
+        for cell in fixture.cells {
+            let out_point = OutPoint::new_builder()
+                .tx_hash(parse_byte32(&cell.out_point.tx_hash)?)
+                .index((parse_u64(&cell.out_point.index)? as u32).pack())
+                .build();
+            let output = CellOutput::new_builder()
+                .capacity(parse_u64(&cell.output.capacity)?.pack())
+                .lock(cell.output.lock.to_script()?)
+                .type_(
+                    cell.output
+                        .type_
+                        .map(|script| script.to_script())
+                        .transpose()?
+                        .pack(),
+                )
+                .build();
+            let data = parse_hex(&cell.data)?.into();
+            state = match cell.block_hash {
+                Some(block_hash) => {
+                    state.with_cell_in_block(out_point, output, data, parse_byte32(&block_hash)?)
+                }
+                None => state.with_cell(out_point, output, data),
+            };
+        }
+        for header in fixture.headers {
+            state = state.with_header(header.to_header()?);
+        }
+        Ok(state)
+    }
+
+    /// Install this chain state for the current thread
+    ///
+    /// The chain state, including the `argv` returned by [`argv`], is per
+    /// thread, so tests loading different states can run in parallel. The
+    /// `argv` is also installed as the global `ckb_std::env::argv` for contract
+    /// code reading it directly; that one is shared by all threads and holds
+    /// the `argv` of the state loaded last. The execution context cached by
+    /// `utils::context` is forgotten for the current thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an argument contains a NUL byte.
+    pub fn load(self) {
+        let argv = self
+            .argv
+            .iter()
+            .map(|arg| {
+                let arg = CString::new(arg.as_str()).expect("argv must not contain NUL bytes");
+                Arg::from(&*Box::leak(arg.into_boxed_c_str()))
+            })
+            .collect::<Vec<_>>();
+        // The arguments are leaked, as `argv` has to outlive any later load
+        let argv: &'static [Arg] = Box::leak(argv.into_boxed_slice());
+        ARGV.with(|cell| cell.set(argv));
+        {
+            let _guard = GLOBAL_ARGV.lock().unwrap_or_else(|err| err.into_inner());
+            unsafe { ckb_std::env::set_argv(argv) };
+        }
+        STATE.with(|state| *state.borrow_mut() = self);
+        CONTENT.with(|content| *content.borrow_mut() = None);
+        crate::utils::context::reset_probes();
+    }
+}
+
+/// Arguments of the call of the chain state loaded on the current thread
+///
+/// Simulated version of `ckb_std::env::argv`; the SSRI dispatch of
+/// `ssri_entry!` and `utils::context` read the arguments from here.
+pub fn argv() -> &'static [Arg] {
+    ARGV.with(|argv| argv.get())
+}
+
+/// Take the content set by the last `set_content` call on the current thread
+pub fn take_content() -> Option<Vec<u8>> {
+    CONTENT.with(|content| content.borrow_mut().take())
+}
+
+/// Serializes the writes of the global `ckb_std::env::argv`
+static GLOBAL_ARGV: Mutex<()> = Mutex::new(());
+
+std::thread_local! {
+    static STATE: RefCell<ChainState> = RefCell::new(ChainState::default());
+    static ARGV: Cell<&'static [Arg]> = const { Cell::new(&[]) };
+    static CONTENT: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
+}
+
+#[derive(Deserialize)]
+struct JsonChainState {
+    vm_version: Option<String>,
+    #[serde(default)]
+    argv: Vec<String>,
+    script: Option<JsonScript>,
+    #[serde(default)]
+    cells: Vec<JsonCell>,
+    #[serde(default)]
+    headers: Vec<JsonHeader>,
+}
+
+#[derive(Deserialize)]
+struct JsonCell {
+    out_point: JsonOutPoint,
+    output: JsonCellOutput,
+    #[serde(default)]
+    data: String,
+    block_hash: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonOutPoint {
+    tx_hash: String,
+    index: String,
+}
+
+#[derive(Deserialize)]
+struct JsonCellOutput {
+    capacity: String,
+    lock: JsonScript,
+    #[serde(rename = "type")]
+    type_: Option<JsonScript>,
+}
+
+#[derive(Deserialize)]
+struct JsonScript {
+    code_hash: String,
+    hash_type: String,
+    args: String,
+}
+
+impl JsonScript {
+    fn to_script(&self) -> Result<Script, String> {
+        let hash_type: u8 = match self.hash_type.as_str() {
+            "data" => 0,
+            "type" => 1,
+            "data1" => 2,
+            "data2" => 4,
+            other => return Err(format!("invalid hash_type `{}`", other)),
+        };
+        Ok(Script::new_builder()
+            .code_hash(parse_byte32(&self.code_hash)?)
+            .hash_type(hash_type.into())
+            .args(parse_hex(&self.args)?.pack())
+            .build())
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonHeader {
+    version: String,
+    compact_target: String,
+    timestamp: String,
+    number: String,
+    epoch: String,
+    parent_hash: String,
+    transactions_root: String,
+    proposals_hash: String,
+    extra_hash: String,
+    dao: String,
+    nonce: String,
+}
+
+impl JsonHeader {
+    fn to_header(&self) -> Result<Header, String> {
+        let raw = RawHeader::new_builder()
+            .version((parse_u64(&self.version)? as u32).pack())
+            .compact_target((parse_u64(&self.compact_target)? as u32).pack())
+            .timestamp(parse_u64(&self.timestamp)?.pack())
+            .number(parse_u64(&self.number)?.pack())
+            .epoch(parse_u64(&self.epoch)?.pack())
+            .parent_hash(parse_byte32(&self.parent_hash)?)
+            .transactions_root(parse_byte32(&self.transactions_root)?)
+            .proposals_hash(parse_byte32(&self.proposals_hash)?)
+            .extra_hash(parse_byte32(&self.extra_hash)?)
+            .dao(parse_byte32(&self.dao)?)
+            .build();
+        let nonce = u128::from_str_radix(self.nonce.strip_prefix("0x").unwrap_or(&self.nonce), 16)
+            .map_err(|err| err.to_string())?;
+        Ok(Header::new_builder().raw(raw).nonce(nonce.pack()).build())
+    }
+}
+
+fn parse_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+    if hex.len() & 1 != 0 || !hex.is_ascii() {
+        return Err(format!("invalid hex `{}`", hex));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|err| err.to_string()))
+        .collect()
+}
+
+fn encode_hex(raw: &[u8]) -> String {
+    raw.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn parse_u64(hex: &str) -> Result<u64, String> {
+    u64::from_str_radix(hex.strip_prefix("0x").unwrap_or(hex), 16).map_err(|err| err.to_string())
+}
+
+fn parse_byte32(hex: &str) -> Result<Byte32, String> {
+    let raw: [u8; 32] = parse_hex(hex)?
+        .try_into()
+        .map_err(|_| format!("invalid 32-byte hash `{}`", hex))?;
+    Ok(raw.pack())
+}
+
+/// Find the `index`-th cell matching `matches`
+fn find_cell_at<T>(
+    matches: impl Fn(&SimulatedCell) -> bool,
+    field: impl Fn(&SimulatedCell) -> T,
+    index: usize,
+) -> Result<T, SysError> {
+    STATE.with(|state| {
+        let state = state.borrow();
+        let mut found = state.cells.iter().filter(|cell| matches(cell));
+        match found.nth(index) {
+            Some(cell) => Ok(field(cell)),
+            None if index == 0 => Err(SysError::ItemMissing),
+            None => Err(SysError::IndexOutOfBound),
+        }
+    })
+}
+
+fn find_cell<T>(
+    matches: impl Fn(&SimulatedCell) -> bool,
+    field: impl Fn(&SimulatedCell) -> T,
+) -> Result<T, SysError> {
+    find_cell_at(matches, field, 0)
+}
+
+pub fn vm_version() -> u64 {
+    STATE.with(|state| state.borrow().vm_version)
+}
+
+/// Load the script of the chain state
+///
+/// Simulated version of `ckb_std::syscalls::load_script`; fails with
+/// `SysError::ItemMissing` if the chain state has no script.
+pub fn load_script(buf: &mut [u8], offset: usize) -> Result<usize, SysError> {
+    let script = STATE.with(|state| state.borrow().script.clone());
+    load_item(buf, script.ok_or(SysError::ItemMissing)?.as_slice(), offset)
+}
+
+/// Load the transaction of the chain state
+///
+/// Simulated version of `ckb_std::syscalls::load_transaction`; fails with
+/// `SysError::ItemMissing` if the chain state has no transaction.
+pub fn load_transaction(buf: &mut [u8], offset: usize) -> Result<usize, SysError> {
+    let transaction = STATE.with(|state| state.borrow().transaction.clone());
+    load_item(buf, transaction.ok_or(SysError::ItemMissing)?.as_slice(), offset)
+}
+
+/// Load the data of a cell of the transaction of the chain state
+///
+/// Simulated version of `ckb_std::syscalls::load_cell_data`. Inputs and cell
+/// deps are looked up among the cells of the chain state, and the group
+/// sources select the cells whose lock or type script is the script of the
+/// chain state. Fails with `SysError::ItemMissing` if the chain state has no
+/// transaction, or if the cell of an input or cell dep is unknown.
+pub fn load_cell_data(
+    buf: &mut [u8],
+    offset: usize,
+    index: usize,
+    source: Source,
+) -> Result<usize, SysError> {
+    let data = STATE.with(|state| {
+        let state = state.borrow();
+        let raw = state.transaction.as_ref().ok_or(SysError::ItemMissing)?.raw();
+        let live_cell = |out_point: OutPoint| {
+            state
+                .cells
+                .iter()
+                .find(|cell| cell.out_point.as_slice() == out_point.as_slice())
+                .map(|cell| (cell.output.clone(), cell.data.clone()))
+                .ok_or(SysError::ItemMissing)
+        };
+        let cells: Vec<Result<(CellOutput, Bytes), SysError>> = match source {
+            Source::Input | Source::GroupInput => raw
+                .inputs()
+                .into_iter()
+                .map(|input| live_cell(input.previous_output()))
+                .collect(),
+            Source::Output | Source::GroupOutput => raw
+                .outputs()
+                .into_iter()
+                .zip(raw.outputs_data())
+                .map(|(output, data)| Ok((output, data.raw_data())))
+                .collect(),
+            Source::CellDep => raw
+                .cell_deps()
+                .into_iter()
+                .map(|cell_dep| live_cell(cell_dep.out_point()))
+                .collect(),
+            Source::HeaderDep => Vec::new(),
+        };
+        let in_group = |output: &CellOutput| {
+            state.script.as_ref().is_some_and(|script| {
+                output.lock().as_slice() == script.as_slice()
+                    || output
+                        .type_()
+                        .to_opt()
+                        .is_some_and(|type_script| type_script.as_slice() == script.as_slice())
+            })
+        };
+        let group = matches!(source, Source::GroupInput | Source::GroupOutput);
+        let mut found = cells.into_iter().filter(|cell| match cell {
+            Ok((output, _)) => !group || in_group(output),
+            Err(_) => true,
+        });
+        match found.nth(index) {
+            Some(cell) => cell.map(|(_, data)| data),
+            None => Err(SysError::IndexOutOfBound),
+        }
+    })?;
+    load_item(buf, &data, offset)
+}
+
+/// Find an OutPoint by searching for a specific type script
+///
+/// Simulated version of the `SYS_FIND_OUT_POINT_BY_TYPE` syscall.
+pub fn find_out_point_by_type(buf: &mut [u8], type_script: &[u8]) -> Result<usize, SysError> {
+    load_out_point_by_type(buf, type_script, 0)
+}
+
+/// Find the OutPoint of the `index`-th cell with a specific type script
+///
+/// Simulated version of the indexed `SYS_FIND_OUT_POINT_BY_TYPE` syscall.
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_type_at(
+    buf: &mut [u8],
+    type_script: &[u8],
+    index: usize,
+) -> Result<usize, SysError> {
+    load_out_point_by_type(buf, type_script, index)
+}
+
+fn load_out_point_by_type(buf: &mut [u8], type_script: &[u8], index: usize) -> Result<usize, SysError> {
+    let out_point = find_cell_at(
+        |cell| {
+            cell.output
+                .type_()
+                .to_opt()
+                .is_some_and(|script| script.as_slice() == type_script)
+        },
+        |cell| cell.out_point.clone(),
+        index,
+    )?;
+    load_item(buf, out_point.as_slice(), 0)
+}
+
+/// Find an OutPoint by searching for a specific lock script
+///
+/// Simulated version of the `SYS_FIND_OUT_POINT_BY_LOCK` syscall.
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock(buf: &mut [u8], lock_script: &[u8]) -> Result<usize, SysError> {
+    find_out_point_by_lock_at(buf, lock_script, 0)
+}
+
+/// Find the OutPoint of the `index`-th cell with a specific lock script
+///
+/// Simulated version of the indexed `SYS_FIND_OUT_POINT_BY_LOCK` syscall.
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock_at(
+    buf: &mut [u8],
+    lock_script: &[u8],
+    index: usize,
+) -> Result<usize, SysError> {
+    let out_point = find_cell_at(
+        |cell| cell.output.lock().as_slice() == lock_script,
+        |cell| cell.out_point.clone(),
+        index,
+    )?;
+    load_item(buf, out_point.as_slice(), 0)
+}
+
+/// Find an OutPoint by searching for a specific pair of lock and type scripts
+///
+/// Simulated version of the `SYS_FIND_OUT_POINT_BY_LOCK_AND_TYPE` syscall.
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock_and_type(
+    buf: &mut [u8],
+    lock_script: &[u8],
+    type_script: &[u8],
+) -> Result<usize, SysError> {
+    find_out_point_by_lock_and_type_at(buf, lock_script, type_script, 0)
+}
+
+/// Find the OutPoint of the `index`-th cell with a specific pair of lock and
+/// type scripts
+///
+/// Simulated version of the indexed `SYS_FIND_OUT_POINT_BY_LOCK_AND_TYPE` syscall.
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_out_point_by_lock_and_type_at(
+    buf: &mut [u8],
+    lock_script: &[u8],
+    type_script: &[u8],
+    index: usize,
+) -> Result<usize, SysError> {
+    let out_point = find_cell_at(
+        |cell| {
+            cell.output.lock().as_slice() == lock_script
+                && cell
+                    .output
+                    .type_()
+                    .to_opt()
+                    .is_some_and(|script| script.as_slice() == type_script)
+        },
+        |cell| cell.out_point.clone(),
+        index,
+    )?;
+    load_item(buf, out_point.as_slice(), 0)
+}
+
+/// Find a cell by its OutPoint
+///
+/// Simulated version of the `SYS_FIND_CELL_BY_OUT_POINT` syscall.
+pub fn find_cell_by_out_point(
+    buf: &mut [u8],
+    out_point: &[u8],
+    offset: usize,
+) -> Result<usize, SysError> {
+    let output = find_cell(
+        |cell| cell.out_point.as_slice() == out_point,
+        |cell| cell.output.clone(),
+    )?;
+    load_item(buf, output.as_slice(), offset)
+}
+
+/// Find cell data by OutPoint
+///
+/// Simulated version of the `SYS_FIND_CELL_DATA_BY_OUT_POINT` syscall.
+pub fn find_cell_data_by_out_point(
+    buf: &mut [u8],
+    out_point: &[u8],
+    offset: usize,
+) -> Result<usize, SysError> {
+    let data = find_cell(
+        |cell| cell.out_point.as_slice() == out_point,
+        |cell| cell.data.clone(),
+    )?;
+    load_item(buf, &data, offset)
+}
+
+#[cfg(feature = "experimental-syscalls")]
+fn header_hash(header: &Header) -> [u8; 32] {
+    ckb_hash::blake2b_256(header.as_slice())
+}
+
+/// Load the header of the tip block
+///
+/// Simulated version of the `SYS_LOAD_TIP_HEADER` syscall.
+#[cfg(feature = "experimental-syscalls")]
+pub fn load_tip_header(buf: &mut [u8]) -> Result<usize, SysError> {
+    let header = STATE.with(|state| {
+        state
+            .borrow()
+            .headers
+            .iter()
+            .max_by_key(|header| header.raw().number().unpack())
+            .cloned()
+            .ok_or(SysError::ItemMissing)
+    })?;
+    load_item(buf, header.as_slice(), 0)
+}
+
+/// Find a header by its block hash
+///
+/// Simulated version of the `SYS_FIND_HEADER_BY_BLOCK_HASH` syscall.
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_header_by_block_hash(buf: &mut [u8], block_hash: &[u8]) -> Result<usize, SysError> {
+    let header = STATE.with(|state| {
+        state
+            .borrow()
+            .headers
+            .iter()
+            .find(|header| header_hash(header) == block_hash)
+            .cloned()
+            .ok_or(SysError::ItemMissing)
+    })?;
+    load_item(buf, header.as_slice(), 0)
+}
+
+/// Find the header of the block that created an OutPoint
+///
+/// Simulated version of the `SYS_FIND_HEADER_BY_OUT_POINT` syscall.
+#[cfg(feature = "experimental-syscalls")]
+pub fn find_header_by_out_point(buf: &mut [u8], out_point: &[u8]) -> Result<usize, SysError> {
+    let block_hash = find_cell(
+        |cell| cell.out_point.as_slice() == out_point,
+        |cell| cell.block_hash.clone(),
+    )?
+    .ok_or(SysError::ItemMissing)?;
+    find_header_by_block_hash(buf, block_hash.as_slice())
+}
+
+/// Set the content returned by an SSRI method
+///
+/// The content can be read back with [`take_content`].
+pub fn set_content(content: &[u8]) -> Result<(), SysError> {
+    CONTENT.with(|cell| *cell.borrow_mut() = Some(content.to_vec()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_std::ckb_types::packed::{CellInput, RawTransaction};
+
+    const HEADER: &str = r#"{
+        "version": "0x0",
+        "compact_target": "0x1e015555",
+        "timestamp": "0x18c8d0ab7e0",
+        "number": "0x2",
+        "epoch": "0x7080018000001",
+        "parent_hash": "0x1111111111111111111111111111111111111111111111111111111111111111",
+        "transactions_root": "0x2222222222222222222222222222222222222222222222222222222222222222",
+        "proposals_hash": "0x3333333333333333333333333333333333333333333333333333333333333333",
+        "extra_hash": "0x4444444444444444444444444444444444444444444444444444444444444444",
+        "dao": "0x5555555555555555555555555555555555555555555555555555555555555555",
+        "nonce": "0x10"
+    }"#;
+
+    fn fixture(hash_type: &str) -> String {
+        format!(
+            r#"{{
+                "vm_version": "0x2",
+                "argv": ["0102030405060708", "ff"],
+                "script": {{
+                    "code_hash": "0x9999999999999999999999999999999999999999999999999999999999999999",
+                    "hash_type": "data1",
+                    "args": "0x01"
+                }},
+                "cells": [{{
+                    "out_point": {{
+                        "tx_hash": "0x6666666666666666666666666666666666666666666666666666666666666666",
+                        "index": "0x1"
+                    }},
+                    "output": {{
+                        "capacity": "0x174876e800",
+                        "lock": {{
+                            "code_hash": "0x7777777777777777777777777777777777777777777777777777777777777777",
+                            "hash_type": "{}",
+                            "args": "0xabcd"
+                        }},
+                        "type": {{
+                            "code_hash": "0x8888888888888888888888888888888888888888888888888888888888888888",
+                            "hash_type": "data2",
+                            "args": "0x"
+                        }}
+                    }},
+                    "data": "0x0a0b"
+                }}],
+                "headers": [{}]
+            }}"#,
+            hash_type, HEADER
+        )
+    }
+
+    #[test]
+    fn from_json_parses_fixture() {
+        let state = ChainState::from_json(&fixture("type")).unwrap();
+        assert_eq!(state.vm_version, 2);
+        assert_eq!(state.argv, ["0102030405060708", "ff"]);
+        let script = state.script.as_ref().unwrap();
+        assert_eq!(script.code_hash(), [0x99; 32].pack());
+        assert_eq!(script.hash_type(), 2.into());
+        assert!(state.transaction.is_none());
+        assert_eq!(state.cells.len(), 1);
+        let cell = &state.cells[0];
+        assert_eq!(cell.out_point.tx_hash(), [0x66; 32].pack());
+        assert_eq!(Unpack::<u32>::unpack(&cell.out_point.index()), 1);
+        assert_eq!(cell.output.capacity().unpack(), 100_000_000_000u64);
+        assert_eq!(cell.output.lock().hash_type(), 1.into());
+        assert_eq!(cell.output.lock().args().raw_data().as_ref(), [0xab, 0xcd]);
+        let type_script = cell.output.type_().to_opt().unwrap();
+        assert_eq!(type_script.code_hash(), [0x88; 32].pack());
+        assert_eq!(type_script.hash_type(), 4.into());
+        assert_eq!(cell.data.as_ref(), [0x0a, 0x0b]);
+        assert_eq!(cell.block_hash, None);
+        assert_eq!(state.headers.len(), 1);
+        let raw = state.headers[0].raw();
+        assert_eq!(raw.number().unpack(), 2u64);
+        assert_eq!(raw.parent_hash(), [0x11; 32].pack());
+        assert_eq!(state.headers[0].nonce().unpack(), 0x10u128);
+    }
+
+    #[test]
+    fn from_json_defaults_optional_fields() {
+        let state = ChainState::from_json("{}").unwrap();
+        assert_eq!(state.vm_version, u64::MAX);
+        assert!(state.argv.is_empty() && state.cells.is_empty() && state.headers.is_empty());
+        assert!(state.script.is_none() && state.transaction.is_none());
+    }
+
+    #[test]
+    fn from_json_rejects_invalid_fixtures() {
+        assert!(ChainState::from_json(&fixture("data3")).is_err());
+        assert!(ChainState::from_json("{\"vm_version\": \"0xzz\"}").is_err());
+        assert!(ChainState::from_json("[]").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "experimental-syscalls")]
+    fn header_lookups() {
+        let state = ChainState::from_json(&fixture("type")).unwrap();
+        let header = state.headers[0].clone();
+        let block_hash = header_hash(&header);
+        let older = header
+            .clone()
+            .as_builder()
+            .raw(header.raw().as_builder().number(1u64.pack()).build())
+            .build();
+        let out_point = OutPoint::new_builder().index(9u32.pack()).build();
+        state
+            .with_header(older.clone())
+            .with_cell_in_block(
+                out_point.clone(),
+                CellOutput::default(),
+                Bytes::new(),
+                block_hash.pack(),
+            )
+            .load();
+
+        let mut buf = [0u8; 1024];
+        let len = find_header_by_block_hash(&mut buf, &block_hash).unwrap();
+        assert_eq!(&buf[..len], header.as_slice());
+        let len = find_header_by_block_hash(&mut buf, &header_hash(&older)).unwrap();
+        assert_eq!(&buf[..len], older.as_slice());
+        assert_eq!(
+            find_header_by_block_hash(&mut buf, &[0; 32]),
+            Err(SysError::ItemMissing)
+        );
+        let len = load_tip_header(&mut buf).unwrap();
+        assert_eq!(&buf[..len], header.as_slice());
+        let len = find_header_by_out_point(&mut buf, out_point.as_slice()).unwrap();
+        assert_eq!(&buf[..len], header.as_slice());
+        // The fixture cell has no block hash
+        let fixture_out_point = OutPoint::new_builder()
+            .tx_hash([0x66; 32].pack())
+            .index(1u32.pack())
+            .build();
+        assert_eq!(
+            find_header_by_out_point(&mut buf, fixture_out_point.as_slice()),
+            Err(SysError::ItemMissing)
+        );
+    }
+
+    #[test]
+    fn load_installs_argv() {
+        ChainState::default()
+            .with_call(0x0807060504030201, &[&[0xab], &[]])
+            .load();
+        let args = argv()
+            .iter()
+            .map(|arg| arg.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(args, ["0102030405060708", "ab", ""]);
+        assert_eq!(
+            crate::utils::dispatch::decode_method_path(argv()).ok(),
+            Some(0x0807060504030201)
+        );
+
+        // Each thread sees the `argv` of its own chain state
+        std::thread::spawn(|| {
+            assert!(argv().is_empty());
+            ChainState::default().with_call(1, &[]).load();
+            assert_eq!(argv().len(), 1);
+        })
+        .join()
+        .unwrap();
+        assert_eq!(argv().len(), 3);
+
+        ChainState::default().load();
+        assert!(argv().is_empty());
+    }
+
+    #[test]
+    fn loads_the_script_and_transaction() {
+        ChainState::default().load();
+        let mut buf = [0u8; 1024];
+        assert_eq!(load_script(&mut buf, 0), Err(SysError::ItemMissing));
+        assert_eq!(load_transaction(&mut buf, 0), Err(SysError::ItemMissing));
+
+        let script = Script::new_builder().args([1u8, 2, 3].pack()).build();
+        let transaction = Transaction::default();
+        ChainState::default()
+            .with_script(script.clone())
+            .with_transaction(transaction.clone())
+            .load();
+        let len = load_script(&mut buf, 0).unwrap();
+        assert_eq!(&buf[..len], script.as_slice());
+        let len = load_script(&mut buf, 4).unwrap();
+        assert_eq!(&buf[..len], &script.as_slice()[4..]);
+        assert_eq!(
+            load_script(&mut [], 0),
+            Err(SysError::LengthNotEnough(script.as_slice().len()))
+        );
+        let len = load_transaction(&mut buf, 0).unwrap();
+        assert_eq!(&buf[..len], transaction.as_slice());
+    }
+
+    #[test]
+    fn loads_the_cell_data_of_the_transaction() {
+        let script = Script::new_builder().args([1u8].pack()).build();
+        let other = Script::new_builder().args([2u8].pack()).build();
+        let output = |type_script: &Script| {
+            CellOutput::new_builder().type_(Some(type_script.clone()).pack()).build()
+        };
+        let out_point = |index: u32| OutPoint::new_builder().index(index.pack()).build();
+        let raw = RawTransaction::new_builder()
+            .inputs(
+                [out_point(0), out_point(1)]
+                    .into_iter()
+                    .map(|out_point| CellInput::new_builder().previous_output(out_point).build())
+                    .pack(),
+            )
+            .outputs(vec![output(&other), output(&script)].pack())
+            .outputs_data([Bytes::from(vec![3u8]), Bytes::from(vec![4u8, 5])].pack())
+            .build();
+        let mut buf = [0u8; 16];
+
+        ChainState::default().load();
+        assert_eq!(load_cell_data(&mut buf, 0, 0, Source::Output), Err(SysError::ItemMissing));
+
+        ChainState::default()
+            .with_cell(out_point(0), output(&script), Bytes::from(vec![1u8]))
+            .with_script(script.clone())
+            .with_transaction(Transaction::new_builder().raw(raw).build())
+            .load();
+        let len = load_cell_data(&mut buf, 0, 1, Source::Output).unwrap();
+        assert_eq!(&buf[..len], [4, 5]);
+        let len = load_cell_data(&mut buf, 1, 0, Source::GroupOutput).unwrap();
+        assert_eq!(&buf[..len], [5]);
+        assert_eq!(
+            load_cell_data(&mut buf, 0, 1, Source::GroupOutput),
+            Err(SysError::IndexOutOfBound)
+        );
+        let len = load_cell_data(&mut buf, 0, 0, Source::GroupInput).unwrap();
+        assert_eq!(&buf[..len], [1]);
+        // The cell of the second input is not in the chain state
+        assert_eq!(load_cell_data(&mut buf, 0, 1, Source::Input), Err(SysError::ItemMissing));
+        assert_eq!(load_cell_data(&mut buf, 0, 0, Source::CellDep), Err(SysError::IndexOutOfBound));
+    }
+}
@@ -0,0 +1,224 @@
+//! Completion of the transactions returned by `UDT.transfer` and `UDT.mint`
+//!
+//! [`UDTTransactionBuilder`] appends a UDT output for each `(lock, amount)`
+//! pair to a partial transaction given by the caller, funds each output with
+//! its minimum occupied capacity, and adds the cell dep of the UDT code.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ckb_ssri_std::utils::tx_builder::UDTTransactionBuilder;
+//!
+//! fn transfer(
+//!     tx: Option<Transaction>,
+//!     to_lock_vec: Vec<Script>,
+//!     to_amount_vec: Vec<u128>,
+//! ) -> Result<Transaction, Error> {
+//!     Ok(UDTTransactionBuilder::new(tx, load_script()?)
+//!         .outputs(to_lock_vec, to_amount_vec)?
+//!         .cell_dep_by_type(code_type_script)?
+//!         .build())
+//! }
+//! ```
+
+use alloc::vec::Vec;
+use ckb_std::ckb_types::{
+    packed::{Byte, Bytes, CellDep, CellOutput, OutPoint, Script, Transaction},
+    prelude::*,
+};
+use ckb_std::error::SysError;
+
+use crate::public_module_traits::udt::UDT_LEN;
+use crate::utils::capacity::occupied_capacity;
+use crate::utils::high_level::find_out_point_by_type_with;
+use crate::utils::syscalls::{DefaultSyscalls, SSRISyscalls};
+use crate::SSRIError;
+
+/// `DepType::Code`, the dep type of a cell dep on a code cell
+const DEP_TYPE_CODE: u8 = 0;
+
+/// Builder merging UDT outputs and the UDT cell dep into a partial transaction
+pub struct UDTTransactionBuilder {
+    tx: Transaction,
+    udt_type: Script,
+    cell_deps: Vec<CellDep>,
+    outputs: Vec<(CellOutput, Bytes)>,
+}
+
+impl UDTTransactionBuilder {
+    /// Start from the partial transaction `tx`, or an empty one, with outputs
+    /// of the UDT identified by `udt_type`
+    pub fn new(tx: Option<Transaction>, udt_type: Script) -> Self {
+        UDTTransactionBuilder {
+            tx: tx.unwrap_or_default(),
+            udt_type,
+            cell_deps: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Append an output holding `amount_vec[i]` locked by `lock_vec[i]` for
+    /// each pair
+    ///
+    /// The amounts are stored in the [`UDT_LEN`] bytes of the output data, and
+    /// each output gets its minimum occupied capacity.
+    ///
+    /// # Errors
+    ///
+    /// * Returns `SSRIError::SSRIMethodsArgsInvalid` if the vectors differ in
+    ///   length
+    pub fn outputs(
+        mut self,
+        lock_vec: Vec<Script>,
+        amount_vec: Vec<u128>,
+    ) -> Result<Self, SSRIError> {
+        if lock_vec.len() != amount_vec.len() {
+            return Err(SSRIError::SSRIMethodsArgsInvalid);
+        }
+        for (lock, amount) in lock_vec.into_iter().zip(amount_vec) {
+            let output = CellOutput::new_builder()
+                .lock(lock)
+                .type_(Some(self.udt_type.clone()).pack())
+                .build();
+            let capacity = occupied_capacity(&output, UDT_LEN);
+            let output = output.as_builder().capacity(capacity.pack()).build();
+            self.outputs.push((output, amount.to_le_bytes().to_vec().pack()));
+        }
+        Ok(self)
+    }
+
+    /// Add a cell dep on the code cell at `out_point`, unless the transaction
+    /// already has it
+    pub fn cell_dep(mut self, out_point: OutPoint) -> Self {
+        let cell_dep = CellDep::new_builder()
+            .out_point(out_point)
+            .dep_type(Byte::new(DEP_TYPE_CODE))
+            .build();
+        let is_same = |dep: &CellDep| dep.as_slice() == cell_dep.as_slice();
+        let present = self.tx.raw().cell_deps().into_iter().any(|dep| is_same(&dep))
+            || self.cell_deps.iter().any(is_same);
+        if !present {
+            self.cell_deps.push(cell_dep);
+        }
+        self
+    }
+
+    /// Add a cell dep on the code cell with type script `code_type`, usually
+    /// the Type ID script of the deployed UDT code
+    ///
+    /// # Errors
+    ///
+    /// * Returns `SysError::ItemMissing` if no cell with this type script is found
+    pub fn cell_dep_by_type(self, code_type: Script) -> Result<Self, SysError> {
+        self.cell_dep_by_type_with(&DefaultSyscalls, code_type)
+    }
+
+    /// Add a cell dep on the code cell with type script `code_type` through the
+    /// given syscall backend
+    ///
+    /// See [`UDTTransactionBuilder::cell_dep_by_type`].
+    pub fn cell_dep_by_type_with<S: SSRISyscalls + ?Sized>(
+        self,
+        syscalls: &S,
+        code_type: Script,
+    ) -> Result<Self, SysError> {
+        let out_point = find_out_point_by_type_with(syscalls, code_type)?;
+        Ok(self.cell_dep(out_point))
+    }
+
+    /// Build the transaction, with the added cell deps and outputs after the
+    /// ones of the partial transaction
+    pub fn build(self) -> Transaction {
+        let raw = self.tx.raw();
+        let cell_deps = raw.cell_deps().as_builder().extend(self.cell_deps).build();
+        let mut outputs = raw.outputs().as_builder();
+        let mut outputs_data = raw.outputs_data().as_builder();
+        for (output, data) in self.outputs {
+            outputs = outputs.push(output);
+            outputs_data = outputs_data.push(data);
+        }
+        let raw = raw
+            .as_builder()
+            .cell_deps(cell_deps)
+            .outputs(outputs.build())
+            .outputs_data(outputs_data.build())
+            .build();
+        self.tx.as_builder().raw(raw).build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::syscalls::MockSyscalls;
+    use alloc::vec;
+
+    fn script(arg: u8) -> Script {
+        Script::new_builder().args([arg].pack()).build()
+    }
+
+    fn out_point(index: u32) -> OutPoint {
+        OutPoint::new_builder().index(index.pack()).build()
+    }
+
+    #[test]
+    fn appends_funded_udt_outputs() {
+        let partial = UDTTransactionBuilder::new(None, script(0))
+            .outputs(vec![script(9)], vec![5])
+            .unwrap()
+            .build();
+        let tx = UDTTransactionBuilder::new(Some(partial), script(0))
+            .outputs(vec![script(1), script(2)], vec![10, 20])
+            .unwrap()
+            .build();
+
+        let outputs = tx.raw().outputs();
+        let outputs_data = tx.raw().outputs_data();
+        assert_eq!(outputs.len(), 3);
+        for (index, (lock, amount)) in [(9, 5u128), (1, 10), (2, 20)].into_iter().enumerate() {
+            let output = outputs.get(index).unwrap();
+            assert_eq!(output.lock().as_slice(), script(lock).as_slice());
+            assert_eq!(output.type_().to_opt().unwrap().as_slice(), script(0).as_slice());
+            let capacity: u64 = output.capacity().unpack();
+            assert_eq!(capacity, occupied_capacity(&output, UDT_LEN));
+            assert_eq!(outputs_data.get(index).unwrap().raw_data(), amount.to_le_bytes().as_slice());
+        }
+    }
+
+    #[test]
+    fn rejects_mismatched_outputs() {
+        assert!(matches!(
+            UDTTransactionBuilder::new(None, script(0)).outputs(vec![script(1)], vec![]),
+            Err(SSRIError::SSRIMethodsArgsInvalid)
+        ));
+    }
+
+    #[test]
+    fn adds_each_cell_dep_once() {
+        let partial = UDTTransactionBuilder::new(None, script(0)).cell_dep(out_point(0)).build();
+        let tx = UDTTransactionBuilder::new(Some(partial), script(0))
+            .cell_dep(out_point(0))
+            .cell_dep(out_point(1))
+            .cell_dep(out_point(1))
+            .build();
+        let cell_deps = tx.raw().cell_deps();
+        assert_eq!(cell_deps.len(), 2);
+        assert_eq!(cell_deps.get(1).unwrap().out_point().as_slice(), out_point(1).as_slice());
+        assert_eq!(u8::from(cell_deps.get(1).unwrap().dep_type()), DEP_TYPE_CODE);
+    }
+
+    #[test]
+    fn finds_the_cell_dep_by_type() {
+        let syscalls = MockSyscalls::default()
+            .with_out_point_by_type(script(7).as_slice(), Ok(out_point(3).as_slice().to_vec()));
+        let tx = UDTTransactionBuilder::new(None, script(0))
+            .cell_dep_by_type_with(&syscalls, script(7))
+            .unwrap()
+            .build();
+        assert_eq!(tx.raw().cell_deps().get(0).unwrap().out_point().as_slice(), out_point(3).as_slice());
+        assert!(matches!(
+            UDTTransactionBuilder::new(None, script(0)).cell_dep_by_type_with(&syscalls, script(8)),
+            Err(SysError::ItemMissing)
+        ));
+    }
+}
@@ -0,0 +1,264 @@
+//! Versions reported by `SSRI.version`
+//!
+//! `SSRI.version` returns the [`SSRIVersion`] of a script: the version of the
+//! SSRI protocol it implements, followed by the version of its own interface
+//! declared with `version: "1.2.0"` in `ssri_entry!` or `ssri_methods!`, or
+//! `#[ssri_module(version = "1.2.0")]`. It is encoded as the molecule struct
+//! `struct SSRIVersion { protocol: byte, major: Uint16, minor: Uint16, patch: Uint16 }`.
+//!
+//! Scripts built before the contract version was introduced return the single
+//! byte `0`, which decodes as protocol version 0 and contract version `0.0.0`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ckb_ssri_std::codec::SSRIDecode;
+//! use ckb_ssri_std::utils::version::{ContractVersion, SSRIVersion, SSRI_PROTOCOL_VERSION};
+//!
+//! let version = SSRIVersion::decode(&response)?;
+//! let required = SSRIVersion {
+//!     protocol: SSRI_PROTOCOL_VERSION,
+//!     contract: ContractVersion::parse("1.2.0"),
+//! };
+//! if !version.is_compatible_with(&required) {
+//!     // The deployed script has breaking changes
+//! }
+//! ```
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::codec::{SSRIDecode, SSRIEncode};
+use crate::SSRIError;
+
+/// Version of the SSRI protocol implemented by this crate
+///
+/// Version 0 is the protocol of scripts whose `SSRI.version` returns a single
+/// byte without a contract version.
+pub const SSRI_PROTOCOL_VERSION: u8 = 1;
+
+/// Semantic version of the interface of a script, e.g. `1.2.0`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ContractVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl ContractVersion {
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        ContractVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parse a `major.minor.patch` version, returning `None` if it is malformed
+    pub const fn try_parse(version: &str) -> Option<Self> {
+        let bytes = version.as_bytes();
+        let mut parts = [0u16; 3];
+        let mut part = 0;
+        let mut digits = 0;
+        let mut index = 0;
+        while index < bytes.len() {
+            let byte = bytes[index];
+            if byte == b'.' {
+                if digits == 0 || part == 2 {
+                    return None;
+                }
+                part += 1;
+                digits = 0;
+            } else if byte.is_ascii_digit() {
+                let value = parts[part] as u32 * 10 + (byte - b'0') as u32;
+                if value > u16::MAX as u32 {
+                    return None;
+                }
+                parts[part] = value as u16;
+                digits += 1;
+            } else {
+                return None;
+            }
+            index += 1;
+        }
+        if digits == 0 || part != 2 {
+            return None;
+        }
+        Some(ContractVersion::new(parts[0], parts[1], parts[2]))
+    }
+
+    /// Parse a `major.minor.patch` version
+    ///
+    /// # Panics
+    ///
+    /// Panics if the version is malformed, which fails the build when called
+    /// in a constant.
+    pub const fn parse(version: &str) -> Self {
+        match Self::try_parse(version) {
+            Some(version) => version,
+            None => panic!("contract versions must be `major.minor.patch`"),
+        }
+    }
+
+    /// Whether a script of this version serves callers written against
+    /// `required` without breaking changes
+    ///
+    /// Following semantic versioning, the major versions must be equal and this
+    /// version must not be older than `required`; for major version 0, the
+    /// minor versions must be equal as well.
+    pub fn is_compatible_with(&self, required: &ContractVersion) -> bool {
+        if self.major != required.major {
+            return false;
+        }
+        if self.major == 0 {
+            self.minor == required.minor && self.patch >= required.patch
+        } else {
+            (self.minor, self.patch) >= (required.minor, required.patch)
+        }
+    }
+}
+
+impl fmt::Display for ContractVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Result of `SSRI.version`: the SSRI protocol version and the contract version
+/// of a script
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SSRIVersion {
+    pub protocol: u8,
+    pub contract: ContractVersion,
+}
+
+impl SSRIVersion {
+    /// The version reported by a script of this crate with the given contract
+    /// version
+    pub const fn new(contract: ContractVersion) -> Self {
+        SSRIVersion {
+            protocol: SSRI_PROTOCOL_VERSION,
+            contract,
+        }
+    }
+
+    /// Whether a script of this version serves callers written against
+    /// `required`
+    ///
+    /// The protocol version must not be older than the required one, and the
+    /// contract version must be compatible as defined by
+    /// [`ContractVersion::is_compatible_with`].
+    pub fn is_compatible_with(&self, required: &SSRIVersion) -> bool {
+        self.protocol >= required.protocol && self.contract.is_compatible_with(&required.contract)
+    }
+}
+
+impl SSRIEncode for SSRIVersion {
+    fn encode(&self) -> Vec<u8> {
+        let ContractVersion {
+            major,
+            minor,
+            patch,
+        } = self.contract;
+        [self.protocol]
+            .into_iter()
+            .chain(major.to_le_bytes())
+            .chain(minor.to_le_bytes())
+            .chain(patch.to_le_bytes())
+            .collect()
+    }
+}
+
+/// Decode the result of `SSRI.version`, including the single byte returned by
+/// scripts of protocol version 0
+impl SSRIDecode for SSRIVersion {
+    fn decode(raw: &[u8]) -> Result<Self, SSRIError> {
+        let version = |index: usize| u16::from_le_bytes([raw[index], raw[index + 1]]);
+        match raw.len() {
+            1 => Ok(SSRIVersion {
+                protocol: raw[0],
+                contract: ContractVersion::default(),
+            }),
+            7 => Ok(SSRIVersion {
+                protocol: raw[0],
+                contract: ContractVersion::new(version(1), version(3), version(5)),
+            }),
+            _ => Err(SSRIError::SSRIMethodsArgsInvalid),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_versions() {
+        const VERSION: ContractVersion = ContractVersion::parse("1.2.0");
+        assert_eq!(VERSION, ContractVersion::new(1, 2, 0));
+        assert_eq!(ContractVersion::try_parse("0.0.0"), Some(ContractVersion::default()));
+        assert_eq!(
+            ContractVersion::try_parse("65535.01.10"),
+            Some(ContractVersion::new(u16::MAX, 1, 10))
+        );
+        for version in [
+            "", "1.2", "1.2.3.4", "1..2", ".1.2", "1.2.", "a.b.c", "1.2.-3", " 1.2.3", "65536.0.0",
+        ] {
+            assert_eq!(ContractVersion::try_parse(version), None, "{}", version);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "contract versions must be `major.minor.patch`")]
+    fn panics_on_malformed_versions() {
+        ContractVersion::parse("1.2");
+    }
+
+    #[test]
+    fn checks_compatibility() {
+        let version = ContractVersion::new(1, 2, 3);
+        assert!(version.is_compatible_with(&ContractVersion::new(1, 2, 3)));
+        assert!(version.is_compatible_with(&ContractVersion::new(1, 1, 9)));
+        assert!(!version.is_compatible_with(&ContractVersion::new(1, 2, 4)));
+        assert!(!version.is_compatible_with(&ContractVersion::new(1, 3, 0)));
+        assert!(!version.is_compatible_with(&ContractVersion::new(0, 2, 3)));
+        assert!(!ContractVersion::new(2, 0, 0).is_compatible_with(&version));
+
+        // Before 1.0.0, minor versions may break compatibility
+        let version = ContractVersion::new(0, 2, 3);
+        assert!(version.is_compatible_with(&ContractVersion::new(0, 2, 1)));
+        assert!(!version.is_compatible_with(&ContractVersion::new(0, 2, 4)));
+        assert!(!version.is_compatible_with(&ContractVersion::new(0, 1, 0)));
+        assert!(!ContractVersion::new(0, 3, 0).is_compatible_with(&version));
+
+        let version = SSRIVersion::new(ContractVersion::new(1, 2, 3));
+        let legacy = SSRIVersion {
+            protocol: 0,
+            contract: ContractVersion::new(1, 0, 0),
+        };
+        assert!(version.is_compatible_with(&legacy));
+        assert!(!legacy.is_compatible_with(&version));
+    }
+
+    #[test]
+    fn encodes_and_decodes_ssri_versions() {
+        let version = SSRIVersion::new(ContractVersion::new(1, 2, 259));
+        assert_eq!(version.encode(), [SSRI_PROTOCOL_VERSION, 1, 0, 2, 0, 3, 1]);
+        assert_eq!(SSRIVersion::decode(&version.encode()).unwrap(), version);
+
+        let legacy = SSRIVersion::decode(&[0]).unwrap();
+        assert_eq!(
+            legacy,
+            SSRIVersion {
+                protocol: 0,
+                contract: ContractVersion::default(),
+            }
+        );
+        for raw in [&[][..], &[1, 0], &[1, 0, 0, 0, 0, 0, 0, 0]] {
+            assert!(matches!(
+                SSRIVersion::decode(raw),
+                Err(SSRIError::SSRIMethodsArgsInvalid)
+            ));
+        }
+    }
+}
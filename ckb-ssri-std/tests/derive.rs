@@ -0,0 +1,50 @@
+extern crate alloc;
+
+use ckb_ssri_std::codec::{SSRIDecode, SSRIEncode};
+use ckb_ssri_std::{SSRIDecode, SSRIEncode, SSRIError};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, SSRIEncode, SSRIDecode, PartialEq, Debug)]
+struct Transfer {
+    amount: u128,
+    memo: Vec<u8>,
+    lock_hash: Option<[u8; 32]>,
+}
+
+fn transfer() -> Transfer {
+    Transfer {
+        amount: 1_000,
+        memo: b"rent".to_vec(),
+        lock_hash: Some([3u8; 32]),
+    }
+}
+
+#[test]
+fn encodes_as_a_molecule_table() {
+    let value = transfer();
+    assert_eq!(value.encode(), serde_molecule::to_vec(&value, false).unwrap());
+    assert_eq!(value.encode()[..4], (value.encode().len() as u32).to_le_bytes());
+}
+
+#[test]
+fn round_trips_derived_types() {
+    let value = transfer();
+    assert_eq!(Transfer::decode(&value.encode()).unwrap(), value);
+    let empty = Transfer {
+        amount: 0,
+        memo: vec![],
+        lock_hash: None,
+    };
+    assert_eq!(Transfer::decode(&empty.encode()).unwrap(), empty);
+}
+
+#[test]
+fn rejects_malformed_tables() {
+    let raw = transfer().encode();
+    for raw in [&raw[..raw.len() - 1], &raw[1..], &[][..]] {
+        assert!(matches!(
+            Transfer::decode(raw),
+            Err(SSRIError::SSRIMethodsArgsInvalid)
+        ));
+    }
+}
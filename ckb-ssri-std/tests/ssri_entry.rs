@@ -0,0 +1,242 @@
+extern crate alloc;
+
+use std::ffi::CString;
+
+use ckb_ssri_std::codec::SSRIEncode;
+use ckb_ssri_std::utils::dispatch::{
+    RESERVED_METHOD_LEVELS, RESERVED_METHOD_PATHS, SSRI_GET_METHODS, SSRI_GET_METHOD_LEVELS,
+    SSRI_VERSION, UNKNOWN_METHOD_LEVEL,
+};
+use ckb_ssri_std::utils::version::{ContractVersion, SSRIVersion};
+use ckb_ssri_std::{ssri_entry, ssri_method_path, ssri_module, SSRIError};
+use ckb_std::env::Arg;
+use ckb_std::error::SysError;
+
+#[repr(i8)]
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Sys = 1,
+    SSRIMethodsNotFound,
+    SSRIMethodsArgsInvalid,
+    SSRIMethodsNotImplemented,
+    SSRIMethodRequireHigherLevel,
+    InvalidVmVersion,
+    Fallback,
+}
+
+impl From<SysError> for Error {
+    fn from(_: SysError) -> Self {
+        Error::Sys
+    }
+}
+
+impl From<SSRIError> for Error {
+    fn from(err: SSRIError) -> Self {
+        match err {
+            SSRIError::SSRIMethodsNotFound => Error::SSRIMethodsNotFound,
+            SSRIError::SSRIMethodsArgsInvalid => Error::SSRIMethodsArgsInvalid,
+            SSRIError::SSRIMethodsNotImplemented => Error::SSRIMethodsNotImplemented,
+            SSRIError::SSRIMethodRequireHigherLevel => Error::SSRIMethodRequireHigherLevel,
+            SSRIError::InvalidVmVersion => Error::InvalidVmVersion,
+        }
+    }
+}
+
+#[ssri_module(name = "UDT", version = "0.3.0")]
+mod udt {
+    use super::Error;
+
+    #[ssri_method]
+    pub fn decimals() -> Result<u8, Error> {
+        Ok(8)
+    }
+
+    #[ssri_method(level = "script")]
+    pub fn balance() -> Result<u128, Error> {
+        Ok(0)
+    }
+
+    #[ssri_method(level = "transaction")]
+    pub fn fee() -> Result<u64, Error> {
+        Ok(1000)
+    }
+}
+
+mod modules {
+    #[ckb_ssri_std::ssri_module(name = "Counter")]
+    pub mod counter {
+        use ckb_ssri_std::SSRIError;
+
+        #[ssri_method]
+        pub fn double(value: u64) -> Result<u64, SSRIError> {
+            value.checked_mul(2).ok_or(SSRIError::SSRIMethodsArgsInvalid)
+        }
+    }
+}
+
+fn fallback() -> Result<(), Error> {
+    Err(Error::Fallback)
+}
+
+ssri_entry! {
+    modules: [udt, modules::counter],
+    version: "1.2.0",
+    fallback: fallback,
+    error: Error,
+}
+
+/// The same modules without an explicit version
+mod unversioned {
+    use super::{fallback, modules, udt, Error};
+
+    ckb_ssri_std::ssri_entry! {
+        modules: [udt, modules::counter],
+        fallback: fallback,
+        error: Error,
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn arg(bytes: &[u8]) -> Arg {
+    let arg = CString::new(hex(bytes)).unwrap().into_boxed_c_str();
+    Arg::from(&*Box::leak(arg))
+}
+
+fn argv(path: u64, args: &[Vec<u8>]) -> Vec<Arg> {
+    [arg(&path.to_le_bytes())]
+        .into_iter()
+        .chain(args.iter().map(|bytes| arg(bytes)))
+        .collect()
+}
+
+#[test]
+fn overrides_the_module_versions() {
+    assert_eq!(SSRI_CONTRACT_VERSION, ContractVersion::new(1, 2, 0));
+    let version = ssri_dispatch(&argv(SSRI_VERSION, &[])).unwrap();
+    assert_eq!(version.as_ref(), SSRIVersion::new(SSRI_CONTRACT_VERSION).encode());
+
+    assert_eq!(unversioned::SSRI_CONTRACT_VERSION, ContractVersion::default());
+    let version = unversioned::ssri_dispatch(&argv(SSRI_VERSION, &[])).unwrap();
+    assert_eq!(version.as_ref(), SSRIVersion::new(ContractVersion::default()).encode());
+}
+
+#[test]
+fn lists_the_methods_of_all_modules() {
+    let methods = ssri_dispatch(&argv(SSRI_GET_METHODS, &[0u64.encode(), 0u64.encode()]));
+    let expected = RESERVED_METHOD_PATHS
+        .iter()
+        .chain(udt::SSRI_METHOD_PATHS.iter())
+        .chain(modules::counter::SSRI_METHOD_PATHS.iter())
+        .copied()
+        .collect::<Vec<u64>>();
+    assert_eq!(methods.unwrap().as_ref(), expected.encode());
+
+    let offset = (RESERVED_METHOD_PATHS.len() + udt::SSRI_METHOD_PATHS.len()) as u64;
+    let methods = ssri_dispatch(&argv(SSRI_GET_METHODS, &[offset.encode(), 1u64.encode()]));
+    assert_eq!(
+        methods.unwrap().as_ref(),
+        vec![ssri_method_path!("Counter.double")].encode()
+    );
+}
+
+#[test]
+fn lists_the_levels_of_all_modules() {
+    let queried = vec![
+        RESERVED_METHOD_PATHS[0],
+        ssri_method_path!("UDT.decimals"),
+        ssri_method_path!("UDT.balance"),
+        ssri_method_path!("UDT.fee"),
+        ssri_method_path!("Counter.double"),
+        ssri_method_path!("Counter.triple"),
+    ];
+    let levels = ssri_dispatch(&argv(SSRI_GET_METHOD_LEVELS, &[queried.encode()]));
+    assert_eq!(
+        levels.unwrap().as_ref(),
+        vec![RESERVED_METHOD_LEVELS[0], 0, 1, 2, 0, UNKNOWN_METHOD_LEVEL].encode()
+    );
+}
+
+#[test]
+fn dispatches_to_each_module() {
+    let decimals = ssri_dispatch(&argv(ssri_method_path!("UDT.decimals"), &[]));
+    assert_eq!(decimals.unwrap().as_ref(), 8u8.encode());
+    let double = ssri_dispatch(&argv(ssri_method_path!("Counter.double"), &[5u64.encode()]));
+    assert_eq!(double.unwrap().as_ref(), 10u64.encode());
+    let overflow = ssri_dispatch(&argv(ssri_method_path!("Counter.double"), &[u64::MAX.encode()]));
+    assert_eq!(overflow.unwrap_err(), Error::SSRIMethodsArgsInvalid);
+    let unknown = ssri_dispatch(&argv(ssri_method_path!("Counter.triple"), &[]));
+    assert_eq!(unknown.unwrap_err(), Error::SSRIMethodsNotFound);
+    assert_eq!(ssri_dispatch(&[]).unwrap_err(), Error::SSRIMethodsNotFound);
+}
+
+#[cfg(feature = "native-simulator")]
+mod entry {
+    use super::*;
+    use ckb_ssri_std::utils::syscalls::{take_content, ChainState};
+    use ckb_std::ckb_types::packed::{Script, Transaction};
+
+    #[test]
+    fn falls_back_without_arguments() {
+        ChainState::default().load();
+        assert_eq!(program_entry(), Error::Fallback as i8);
+        assert_eq!(take_content(), None);
+
+        // Regular on-chain validation falls back outside the SSRI-VM as well
+        ChainState::default().with_vm_version(2).load();
+        assert_eq!(program_entry(), Error::Fallback as i8);
+    }
+
+    #[test]
+    fn serves_the_call_in_argv() {
+        ChainState::default()
+            .with_call(ssri_method_path!("Counter.double"), &[&21u64.encode()])
+            .load();
+        assert_eq!(program_entry(), 0);
+        assert_eq!(take_content(), Some(42u64.encode()));
+
+        ChainState::default()
+            .with_call(ssri_method_path!("Counter.triple"), &[])
+            .load();
+        assert_eq!(program_entry(), Error::SSRIMethodsNotFound as i8);
+        assert_eq!(take_content(), None);
+    }
+
+    #[test]
+    fn serves_methods_once_their_level_is_supplied() {
+        let balance = ssri_method_path!("UDT.balance");
+        ChainState::default().with_call(balance, &[]).load();
+        assert_eq!(program_entry(), Error::SSRIMethodRequireHigherLevel as i8);
+        ChainState::default()
+            .with_script(Script::default())
+            .with_call(balance, &[])
+            .load();
+        assert_eq!(program_entry(), 0);
+        assert_eq!(take_content(), Some(0u128.encode()));
+
+        let fee = ssri_method_path!("UDT.fee");
+        ChainState::default()
+            .with_script(Script::default())
+            .with_call(fee, &[])
+            .load();
+        assert_eq!(program_entry(), Error::SSRIMethodRequireHigherLevel as i8);
+        ChainState::default()
+            .with_script(Script::default())
+            .with_transaction(Transaction::default())
+            .with_call(fee, &[])
+            .load();
+        assert_eq!(program_entry(), 0);
+        assert_eq!(take_content(), Some(1000u64.encode()));
+    }
+
+    #[test]
+    fn rejects_calls_outside_the_ssri_vm() {
+        ChainState::default()
+            .with_vm_version(2)
+            .with_call(ssri_method_path!("UDT.decimals"), &[])
+            .load();
+        assert_eq!(program_entry(), Error::InvalidVmVersion as i8);
+    }
+}
@@ -0,0 +1,143 @@
+extern crate alloc;
+
+use std::ffi::CString;
+
+use alloc::borrow::Cow;
+use ckb_ssri_std::codec::{decode_arg, SSRIEncode};
+use ckb_ssri_std::utils::version::{ContractVersion, SSRIVersion};
+use ckb_ssri_std::{ssri_methods, SSRIError};
+use ckb_std::env::Arg;
+
+fn dispatch(argv: &[Arg]) -> Result<Cow<'static, [u8]>, SSRIError> {
+    ssri_methods!(
+        argv: argv,
+        #[ssri_method(args = 1)]
+        "Counter.double" => Ok(decode_arg::<u64>(&argv[1])? * 2),
+        #[ssri_method(args = 0)]
+        "Counter.zero" => Ok(0u64),
+        "Counter.count" => Ok(argv.len() as u64 - 1),
+        "Counter.add" => |a: u64, b: u64| -> Result<u64, SSRIError> { Ok(a + b) },
+    )
+}
+
+#[derive(Debug, PartialEq)]
+enum Error {
+    UnknownMethod,
+    BadArguments,
+    Converted,
+}
+
+impl From<SSRIError> for Error {
+    fn from(_: SSRIError) -> Self {
+        Error::Converted
+    }
+}
+
+/// Reports its own errors for unknown methods and malformed arguments
+fn dispatch_with_errors(argv: &[Arg]) -> Result<Cow<'static, [u8]>, Error> {
+    ssri_methods!(
+        argv: argv,
+        version: "2.1.3",
+        error: Error,
+        invalid_method: Error::UnknownMethod,
+        invalid_args: Error::BadArguments,
+        "Counter.half" => |value: u64| -> Result<u64, Error> { Ok(value / 2) },
+    )
+}
+
+/// Converts the errors for unknown methods and malformed arguments from `SSRIError`
+fn dispatch_with_converted_errors(argv: &[Arg]) -> Result<Cow<'static, [u8]>, Error> {
+    ssri_methods!(
+        argv: argv,
+        error: Error,
+        "Counter.half" => |value: u64| -> Result<u64, Error> { Ok(value / 2) },
+    )
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn arg(bytes: &[u8]) -> Arg {
+    let arg = CString::new(hex(bytes)).unwrap().into_boxed_c_str();
+    Arg::from(&*Box::leak(arg))
+}
+
+fn argv(path: u64, args: &[Vec<u8>]) -> Vec<Arg> {
+    [arg(&path.to_le_bytes())]
+        .into_iter()
+        .chain(args.iter().map(|bytes| arg(bytes)))
+        .collect()
+}
+
+fn path(name: &str) -> u64 {
+    u64::from_le_bytes(ckb_hash::blake2b_256(name)[..8].try_into().unwrap())
+}
+
+fn call(name: &str, args: &[Vec<u8>]) -> Result<Cow<'static, [u8]>, SSRIError> {
+    dispatch(&argv(path(name), args))
+}
+
+#[test]
+fn checks_the_declared_argument_count() {
+    assert_eq!(call("Counter.double", &[4u64.encode()]).unwrap().as_ref(), 8u64.encode());
+    for args in [vec![], vec![4u64.encode(), 1u64.encode()]] {
+        assert!(matches!(
+            call("Counter.double", &args),
+            Err(SSRIError::SSRIMethodsArgsInvalid)
+        ));
+    }
+    assert_eq!(call("Counter.zero", &[]).unwrap().as_ref(), 0u64.encode());
+    assert!(matches!(
+        call("Counter.zero", &[vec![]]),
+        Err(SSRIError::SSRIMethodsArgsInvalid)
+    ));
+}
+
+#[test]
+fn leaves_undeclared_arguments_to_the_body() {
+    for count in 0..3u64 {
+        let args = vec![vec![]; count as usize];
+        assert_eq!(call("Counter.count", &args).unwrap().as_ref(), count.encode());
+    }
+}
+
+#[test]
+fn checks_the_parameter_count_of_closures() {
+    let add = call("Counter.add", &[2u64.encode(), 3u64.encode()]);
+    assert_eq!(add.unwrap().as_ref(), 5u64.encode());
+    for args in [vec![2u64.encode()], vec![2u64.encode(); 3], vec![2u64.encode(), vec![1]]] {
+        assert!(matches!(
+            call("Counter.add", &args),
+            Err(SSRIError::SSRIMethodsArgsInvalid)
+        ));
+    }
+}
+
+#[test]
+fn reports_the_configured_errors() {
+    let half = argv(path("Counter.half"), &[8u64.encode()]);
+    assert_eq!(dispatch_with_errors(&half).unwrap().as_ref(), 4u64.encode());
+    let unknown = argv(path("Counter.double"), &[8u64.encode()]);
+    assert_eq!(dispatch_with_errors(&unknown).unwrap_err(), Error::UnknownMethod);
+    assert_eq!(dispatch_with_errors(&[]).unwrap_err(), Error::UnknownMethod);
+    let malformed = argv(path("Counter.half"), &[8u32.encode()]);
+    assert_eq!(dispatch_with_errors(&malformed).unwrap_err(), Error::BadArguments);
+    let reserved = argv(path("SSRI.version"), &[vec![]]);
+    assert_eq!(dispatch_with_errors(&reserved).unwrap_err(), Error::BadArguments);
+
+    assert_eq!(dispatch_with_converted_errors(&half).unwrap().as_ref(), 4u64.encode());
+    assert_eq!(dispatch_with_converted_errors(&unknown).unwrap_err(), Error::Converted);
+    assert_eq!(dispatch_with_converted_errors(&malformed).unwrap_err(), Error::Converted);
+}
+
+#[test]
+fn reports_the_declared_version() {
+    let version = dispatch_with_errors(&argv(path("SSRI.version"), &[])).unwrap();
+    assert_eq!(
+        version.as_ref(),
+        SSRIVersion::new(ContractVersion::new(2, 1, 3)).encode()
+    );
+    let version = call("SSRI.version", &[]).unwrap();
+    assert_eq!(version.as_ref(), SSRIVersion::new(ContractVersion::default()).encode());
+}
@@ -0,0 +1,130 @@
+extern crate alloc;
+
+use std::ffi::CString;
+
+use ckb_ssri_std::codec::SSRIEncode;
+use ckb_ssri_std::utils::dispatch::{RESERVED_METHOD_PATHS, SSRI_GET_METHODS, SSRI_VERSION};
+use ckb_ssri_std::utils::version::{ContractVersion, SSRIVersion};
+use ckb_ssri_std::{ssri_method_path, ssri_module, SSRIError};
+use ckb_std::env::Arg;
+
+#[ssri_module(name = "Counter", version = "1.2.0")]
+mod counter {
+    use ckb_ssri_std::SSRIError;
+
+    // `ssri_method` is not imported here: the module only compiles because
+    // `#[ssri_module]` strips the attribute from the methods
+    #[ssri_method]
+    pub fn double(value: u64) -> Result<u64, SSRIError> {
+        value.checked_mul(2).ok_or(SSRIError::SSRIMethodsArgsInvalid)
+    }
+
+    // Parameters taken by reference are decoded as the referenced type
+    #[allow(clippy::ptr_arg)]
+    #[ssri_method]
+    pub fn sum(values: &Vec<u64>) -> Result<u64, SSRIError> {
+        Ok(values.iter().sum())
+    }
+
+    #[ssri_method(level = "transaction")]
+    pub fn fee() -> Result<u64, SSRIError> {
+        Ok(0)
+    }
+
+    pub fn helper() -> u64 {
+        1
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn arg(bytes: &[u8]) -> Arg {
+    let arg = CString::new(hex(bytes)).unwrap().into_boxed_c_str();
+    Arg::from(&*Box::leak(arg))
+}
+
+fn argv(path: u64, args: &[Vec<u8>]) -> Vec<Arg> {
+    [arg(&path.to_le_bytes())]
+        .into_iter()
+        .chain(args.iter().map(|bytes| arg(bytes)))
+        .collect()
+}
+
+#[test]
+fn lists_the_methods() {
+    assert_eq!(
+        counter::SSRI_METHOD_NAMES,
+        ["Counter.double", "Counter.sum", "Counter.fee"]
+    );
+    assert_eq!(
+        counter::SSRI_METHOD_PATHS,
+        [
+            ssri_method_path!("Counter.double"),
+            ssri_method_path!("Counter.sum"),
+            ssri_method_path!("Counter.fee"),
+        ]
+    );
+    assert_eq!(counter::SSRI_METHOD_LEVELS, [0, 0, 2]);
+    assert_eq!(counter::SSRI_CONTRACT_VERSION, ContractVersion::new(1, 2, 0));
+    assert_eq!(counter::helper(), 1);
+}
+
+#[test]
+fn calls_the_methods() {
+    let double = ssri_method_path!("Counter.double");
+    let res = counter::ssri_call(double, &argv(double, &[21u64.encode()]));
+    assert_eq!(res.unwrap().unwrap().as_ref(), 42u64.encode());
+
+    let sum = ssri_method_path!("Counter.sum");
+    let res = counter::ssri_call(sum, &argv(sum, &[vec![1u64, 2, 3].encode()]));
+    assert_eq!(res.unwrap().unwrap().as_ref(), 6u64.encode());
+
+    assert!(counter::ssri_call(ssri_method_path!("Counter.helper"), &argv(double, &[])).is_none());
+    assert!(counter::ssri_call(SSRI_VERSION, &argv(SSRI_VERSION, &[])).is_none());
+}
+
+#[test]
+fn rejects_malformed_arguments() {
+    let double = ssri_method_path!("Counter.double");
+    for args in [vec![], vec![21u64.encode(), 1u64.encode()], vec![21u32.encode()]] {
+        assert!(matches!(
+            counter::ssri_call(double, &argv(double, &args)),
+            Some(Err(SSRIError::SSRIMethodsArgsInvalid))
+        ));
+    }
+    let overflow = counter::ssri_call(double, &argv(double, &[u64::MAX.encode()]));
+    assert!(matches!(overflow, Some(Err(SSRIError::SSRIMethodsArgsInvalid))));
+}
+
+#[test]
+fn dispatches_reserved_and_module_methods() {
+    let version = counter::ssri_dispatch(&argv(SSRI_VERSION, &[])).unwrap();
+    assert_eq!(
+        version.as_ref(),
+        SSRIVersion::new(ContractVersion::new(1, 2, 0)).encode()
+    );
+
+    let methods = counter::ssri_dispatch(&argv(SSRI_GET_METHODS, &[0u64.encode(), 0u64.encode()]));
+    let expected = RESERVED_METHOD_PATHS
+        .iter()
+        .chain(counter::SSRI_METHOD_PATHS.iter())
+        .copied()
+        .collect::<Vec<u64>>();
+    assert_eq!(methods.unwrap().as_ref(), expected.encode());
+
+    let double = ssri_method_path!("Counter.double");
+    let res = counter::ssri_dispatch(&argv(double, &[4u64.encode()]));
+    assert_eq!(res.unwrap().as_ref(), 8u64.encode());
+
+    let unknown = ssri_method_path!("Counter.helper");
+    assert!(matches!(
+        counter::ssri_dispatch(&argv(unknown, &[])),
+        Err(SSRIError::SSRIMethodsNotFound)
+    ));
+    assert!(matches!(
+        counter::ssri_dispatch(&[arg(b"path")]),
+        Err(SSRIError::SSRIMethodsNotFound)
+    ));
+}